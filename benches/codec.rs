@@ -0,0 +1,107 @@
+use std::{fs, path::PathBuf};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use qoi::{
+    decode_to_pix, decode_to_u8, encode_from_pix, encode_from_pix_scalar, Pixel, PixelLayout,
+};
+
+const WIDTH: u32 = 1920;
+const HEIGHT: u32 = 1080;
+
+/// Something like a UI screenshot: large flat areas with a few details.
+fn screenshot() -> Vec<Pixel> {
+    let background = Pixel {
+        r: 240,
+        g: 240,
+        b: 240,
+        a: 255,
+    };
+    (0..HEIGHT)
+        .flat_map(|y| {
+            (0..WIDTH).map(move |x| {
+                if y % 40 < 20 && x % 300 < 8 {
+                    Pixel {
+                        r: (x % 256) as u8,
+                        g: (y % 256) as u8,
+                        b: 0,
+                        a: 255,
+                    }
+                } else {
+                    background
+                }
+            })
+        })
+        .collect()
+}
+
+/// Smooth gradients with short runs, where most pixels are diffs.
+fn gradient() -> Vec<Pixel> {
+    (0..HEIGHT)
+        .flat_map(|y| {
+            (0..WIDTH).map(move |x| Pixel {
+                r: (x / 8) as u8,
+                g: (y / 4) as u8,
+                b: ((x + y) / 16) as u8,
+                a: 255,
+            })
+        })
+        .collect()
+}
+
+fn images() -> [(&'static str, Vec<Pixel>); 2] {
+    [("screenshot", screenshot()), ("gradient", gradient())]
+}
+
+fn encode(c: &mut Criterion) {
+    for (name, pixels) in images() {
+        let mut group = c.benchmark_group(format!("encode/{}", name));
+        group.bench_function("simd", |b| {
+            b.iter(|| encode_from_pix(black_box(&pixels), WIDTH, HEIGHT))
+        });
+        group.bench_function("scalar", |b| {
+            b.iter(|| encode_from_pix_scalar(black_box(&pixels), WIDTH, HEIGHT))
+        });
+        group.finish();
+    }
+}
+
+/// The vendored conformance images, and every `.qoi` file in the directory `QOI_BENCH_IMAGES`
+/// points to, for example the standard QOI test images.
+fn encoded_images() -> Vec<(String, Vec<u8>)> {
+    let mut dirs = vec![PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/conformance")];
+    dirs.extend(std::env::var_os("QOI_BENCH_IMAGES").map(PathBuf::from));
+    let mut files = dirs
+        .iter()
+        .flat_map(|dir| fs::read_dir(dir).expect("cannot read image directory"))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "qoi"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read(path).unwrap())
+        })
+        .collect()
+}
+
+fn decode(c: &mut Criterion) {
+    let synthetic = images()
+        .into_iter()
+        .map(|(name, pixels)| (name.to_string(), encode_from_pix(&pixels, WIDTH, HEIGHT)));
+    for (name, encoded) in synthetic.chain(encoded_images()) {
+        let mut group = c.benchmark_group(format!("decode/{}", name));
+        group.bench_function("pixels", |b| b.iter(|| decode_to_pix(black_box(&encoded))));
+        group.bench_function("rgba", |b| {
+            b.iter(|| decode_to_u8(black_box(&encoded), PixelLayout::RGBA))
+        });
+        group.bench_function("reference rgba", |b| {
+            b.iter(|| qoi_reference::decode(black_box(&encoded), 4).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
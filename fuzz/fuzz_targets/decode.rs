@@ -0,0 +1,62 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qoi::{decode_into, try_decode, Decoder, Pixel, PixelLayout, SinkDecoder};
+
+// try_decode is the fallible decode_to_pix, the other decoders have to agree with it
+fuzz_target!(|data: &[u8]| {
+    let decoded = try_decode(data);
+
+    // pushed in two pieces, split at a point picked by the input
+    let split = data
+        .first()
+        .map_or(0, |&byte| byte as usize)
+        .min(data.len());
+    let mut pixels = Vec::new();
+    let mut sink = SinkDecoder::new();
+    let mut put = |_, _, pixel: Pixel| pixels.push(pixel);
+    let pushed = sink
+        .push(&data[..split], &mut put)
+        .and_then(|_| sink.push(&data[split..], &mut put))
+        .and_then(|_| sink.finish());
+
+    match &decoded {
+        Ok(image) => {
+            assert_eq!(
+                image.pixels.len(),
+                image.header.width as usize * image.header.height as usize
+            );
+            assert_eq!(pushed.as_ref(), Ok(&image.header));
+            assert_eq!(pixels, image.pixels);
+
+            let mut buffer = vec![0; image.pixels.len() * 4];
+            let stride = image.header.width as usize * 4;
+            assert_eq!(
+                decode_into(data, &mut buffer, stride, PixelLayout::RGBA).as_ref(),
+                Ok(&image.header)
+            );
+            assert_eq!(buffer, image.to_u8());
+        }
+        Err(_) => assert!(pushed.is_err()),
+    }
+
+    if let Ok(mut decoder) = Decoder::new(data) {
+        let width = decoder.header().width as usize;
+        // a huge header must not allocate a huge row, and empty rows would never run out of input
+        if (1..=1 << 16).contains(&width) {
+            let mut row = vec![Pixel::default(); width];
+            let mut rows = 0;
+            loop {
+                match decoder.read_row(&mut row) {
+                    Ok(true) => rows += 1,
+                    Ok(false) => {
+                        let image = decoded.as_ref().expect("the stream decoder accepted it");
+                        assert_eq!(rows, image.header.height);
+                        break;
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qoi::QoiHeader;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(header) = QoiHeader::from_u8(data) {
+        assert!(data.len() >= 14);
+        assert_eq!(&data[..4], b"qoif");
+        assert_eq!(
+            header.width,
+            u32::from_be_bytes([data[4], data[5], data[6], data[7]])
+        );
+    }
+});
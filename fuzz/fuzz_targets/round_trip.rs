@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use qoi::{try_decode, try_encode, Pixel};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&width, data)) = data.split_first() else {
+        return;
+    };
+    let width = width as usize + 1;
+    let pixels = data
+        .chunks_exact(4)
+        .map(|pixel| Pixel {
+            r: pixel[0],
+            g: pixel[1],
+            b: pixel[2],
+            a: pixel[3],
+        })
+        .collect::<Vec<_>>();
+    let height = pixels.len() / width;
+    let pixels = &pixels[..width * height];
+
+    let encoded = try_encode(pixels, width as u32, height as u32).unwrap();
+    let decoded = try_decode(&encoded).unwrap();
+
+    assert_eq!(decoded.pixels, pixels);
+});
@@ -0,0 +1,216 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, fmt::Write, ops::RangeInclusive, str::FromStr};
+
+use crate::{header, tokenize, Channels, ColorSpace, Op, QoiHeader, END_MARKER};
+
+/// A line of [`assemble`] input that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// Starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl core::error::Error for AsmError {}
+
+/// Turns the text format written by [`disassemble`] into a QOI stream.
+///
+/// The first line is the header, `HEADER <width> <height> <RGB|RGBA> <SRGB|LINEAR>`,
+/// followed by one op per line:
+/// - `RGB <r> <g> <b>` and `RGBA <r> <g> <b> <a>`
+/// - `INDEX <0..=63>`
+/// - `DIFF <dr> <dg> <db>`, each in -2..=1
+/// - `LUMA <dg> <dr - dg> <db - dg>`, with dg in -32..=31 and the others in -8..=7
+/// - `RUN <1..=62>`
+/// - `END` for the end marker
+///
+/// Mnemonics are case insensitive, and everything after a `#` is a comment.
+/// Nothing is checked beyond the syntax of every line, so the header does not have to match
+/// the ops and the end marker can be left out or put in the middle, to write broken streams on purpose.
+pub fn assemble(text: &str) -> Result<Vec<u8>, AsmError> {
+    let mut encoded = Vec::new();
+    let mut has_header = false;
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| AsmError {
+            line: i + 1,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let Some(mnemonic) = words.next() else {
+            continue;
+        };
+        let mnemonic = mnemonic.to_ascii_uppercase();
+        let args = words.collect::<Vec<_>>();
+
+        if !has_header {
+            if mnemonic != "HEADER" {
+                return Err(error("expected HEADER first".to_string()));
+            }
+            let [width, height, channels, color_space] = args[..] else {
+                return Err(error(
+                    "HEADER takes a width, a height, channels and a color space".to_string(),
+                ));
+            };
+            let channels = match channels.to_ascii_uppercase().as_str() {
+                "RGB" => Channels::RGB,
+                "RGBA" => Channels::RGBA,
+                other => return Err(error(format!("invalid channels {:?}", other))),
+            };
+            let color_space = match color_space.to_ascii_uppercase().as_str() {
+                "SRGB" => ColorSpace::SRGB,
+                "LINEAR" => ColorSpace::Linear,
+                other => return Err(error(format!("invalid color space {:?}", other))),
+            };
+            encoded.extend_from_slice(&header(
+                number(width, 0..=u32::MAX).map_err(error)?,
+                number(height, 0..=u32::MAX).map_err(error)?,
+                channels,
+                color_space,
+            ));
+            has_header = true;
+            continue;
+        }
+
+        if mnemonic == "END" {
+            if !args.is_empty() {
+                return Err(error("END takes no arguments".to_string()));
+            }
+            encoded.extend_from_slice(&END_MARKER);
+            continue;
+        }
+        parse_op(&mnemonic, &args)
+            .map_err(error)?
+            .append_to(&mut encoded)
+            .map_err(|err| error(err.to_string()))?;
+    }
+    if !has_header {
+        return Err(AsmError {
+            line: text.lines().count() + 1,
+            message: "missing HEADER".to_string(),
+        });
+    }
+    Ok(encoded)
+}
+
+fn parse_op(mnemonic: &str, args: &[&str]) -> Result<Op, String> {
+    let expected = match mnemonic {
+        "RGB" | "DIFF" | "LUMA" => 3,
+        "RGBA" => 4,
+        "INDEX" | "RUN" => 1,
+        _ => return Err(format!("unknown op {:?}", mnemonic)),
+    };
+    if args.len() != expected {
+        return Err(format!("{} takes {} arguments", mnemonic, expected));
+    }
+    let byte = |i: usize| number(args[i], 0..=255);
+    let op = match mnemonic {
+        "RGB" => Op::RGB {
+            r: byte(0)?,
+            g: byte(1)?,
+            b: byte(2)?,
+        },
+        "RGBA" => Op::RGBA {
+            r: byte(0)?,
+            g: byte(1)?,
+            b: byte(2)?,
+            a: byte(3)?,
+        },
+        "INDEX" => Op::Index(number(args[0], 0..=63)?),
+        "DIFF" => Op::Diff {
+            dr: number(args[0], -2..=1)?,
+            dg: number(args[1], -2..=1)?,
+            db: number(args[2], -2..=1)?,
+        },
+        "LUMA" => Op::Luma {
+            dg: number(args[0], -32..=31)?,
+            dr_dg: number(args[1], -8..=7)?,
+            db_dg: number(args[2], -8..=7)?,
+        },
+        _ => Op::Run(number(args[0], 1..=62)?),
+    };
+    Ok(op)
+}
+
+fn number<T: FromStr + PartialOrd + fmt::Display>(
+    word: &str,
+    range: RangeInclusive<T>,
+) -> Result<T, String> {
+    match word.parse() {
+        Ok(value) if range.contains(&value) => Ok(value),
+        _ => Err(format!(
+            "expected a number in {}..={}, got {:?}",
+            range.start(),
+            range.end(),
+            word
+        )),
+    }
+}
+
+/// Writes the ops of `encoded` in the text format read by [`assemble`], one per line.
+///
+/// If the stream is invalid, everything up to the problem is written,
+/// followed by a comment with the error. Data after the end marker is left out.
+pub fn disassemble(encoded: &[u8]) -> String {
+    let mut text = String::new();
+    match QoiHeader::from_u8(encoded) {
+        Ok(header) => {
+            let channels = match header.channels {
+                Channels::RGB => "RGB",
+                Channels::RGBA => "RGBA",
+            };
+            let color_space = match header.color_space {
+                ColorSpace::SRGB => "SRGB",
+                ColorSpace::Linear => "LINEAR",
+            };
+            // writing to a String cannot fail
+            let _ = writeln!(
+                text,
+                "HEADER {} {} {} {}",
+                header.width, header.height, channels, color_space
+            );
+        }
+        Err(err) => {
+            let _ = writeln!(text, "# error: {}", err);
+            return text;
+        }
+    }
+
+    for token in tokenize(encoded) {
+        match token {
+            Ok((_, op)) => {
+                let _ = writeln!(text, "{}", op);
+            }
+            Err(err) => {
+                let _ = writeln!(text, "# error: {}", err);
+                return text;
+            }
+        }
+    }
+    text.push_str("END\n");
+    text
+}
+
+impl fmt::Display for Op {
+    /// Formats the op as a line of the text format read by [`assemble`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::RGB { r, g, b } => write!(f, "RGB {} {} {}", r, g, b),
+            Op::RGBA { r, g, b, a } => write!(f, "RGBA {} {} {} {}", r, g, b, a),
+            Op::Index(index) => write!(f, "INDEX {}", index),
+            Op::Diff { dr, dg, db } => write!(f, "DIFF {} {} {}", dr, dg, db),
+            Op::Luma { dg, dr_dg, db_dg } => write!(f, "LUMA {} {} {}", dg, dr_dg, db_dg),
+            Op::Run(run) => write!(f, "RUN {}", run),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{Chunk, DecodeFailure, Pixel, QOIHash, QoiError, END_MARKER};
+
+/// Everything that has to be carried over from one op to the next while decoding.
+#[derive(Debug, Clone)]
+pub(crate) struct DecoderState {
+    hash: QOIHash,
+    pub(crate) previous: Pixel,
+}
+
+impl DecoderState {
+    pub(crate) fn new() -> Self {
+        DecoderState {
+            hash: QOIHash::new(),
+            previous: Pixel::default(),
+        }
+    }
+
+    /// Applies `op` and returns how many pixels it produces.
+    /// All of them are equal to `previous` afterwards.
+    pub(crate) fn decode_chunk(&mut self, op: &Chunk) -> usize {
+        match op {
+            Chunk::RGB(rgb) => {
+                // alpha stays the same as in the previous pixel
+                let pixel = Pixel {
+                    r: rgb.r,
+                    g: rgb.g,
+                    b: rgb.b,
+                    a: self.previous.a,
+                };
+                self.hash.insert(&pixel);
+                self.previous = pixel;
+                1
+            }
+            Chunk::RGBA(rgba) => {
+                let pixel = Pixel {
+                    r: rgba.r,
+                    g: rgba.g,
+                    b: rgba.b,
+                    a: rgba.a,
+                };
+                self.hash.insert(&pixel);
+                self.previous = pixel;
+                1
+            }
+            Chunk::Index(index) => {
+                // inserted again like in the reference decoder, which only matters for a
+                // zero pixel read from a slot it was not hashed to
+                let pixel = self.hash.get(index.index);
+                self.hash.insert(&pixel);
+                self.previous = pixel;
+                1
+            }
+            Chunk::Diff(diff) => {
+                let diff = diff.get_diffs();
+                self.apply_diff(diff);
+                1
+            }
+            Chunk::Luma(luma) => {
+                let diff = luma.get_diffs();
+                self.apply_diff(diff);
+                1
+            }
+            Chunk::Run(run) => {
+                // the first op may be a run of the initial pixel, which is not in the hash yet
+                self.hash.insert(&self.previous);
+                run.run as usize + 1
+            }
+        }
+    }
+
+    fn apply_diff(&mut self, diff: (i8, i8, i8)) {
+        let previous = self.previous;
+        let pixel = Pixel {
+            r: previous.r.wrapping_add_signed(diff.0),
+            g: previous.g.wrapping_add_signed(diff.1),
+            b: previous.b.wrapping_add_signed(diff.2),
+            a: previous.a,
+        };
+        self.hash.insert(&pixel);
+        self.previous = pixel;
+    }
+}
+
+/// Decodes the ops after the header of `encoded` and checks the end marker.
+///
+/// `put` is called with every decoded pixel and how many times it repeats,
+/// runs that go past `num_pixels` are cut off.
+///
+/// This is the hot loop behind all slice based decoding, so it dispatches on the tag byte directly
+/// instead of going through [`Chunk`], and only checks the bounds of `encoded` once per op.
+pub(crate) fn decode_ops(
+    encoded: &[u8],
+    num_pixels: usize,
+    put: impl FnMut(Pixel, usize),
+) -> Result<(), QoiError> {
+    decode_ops_at(encoded, num_pixels, put).map_err(|failure| failure.error)
+}
+
+/// Like [`decode_ops`], but also tells where decoding failed.
+pub(crate) fn decode_ops_at(
+    encoded: &[u8],
+    num_pixels: usize,
+    mut put: impl FnMut(Pixel, usize),
+) -> Result<(), DecodeFailure> {
+    let mut hash = QOIHash::new();
+    let mut pixel = Pixel::default();
+    let mut remaining = num_pixels;
+
+    let mut i = 14; // which byte in encoded
+    while remaining > 0 {
+        // no op is longer than 5 bytes, and only the last ones of a truncated stream are closer to the end
+        let op = match encoded.get(i..i + 5) {
+            Some(op) => [op[0], op[1], op[2], op[3], op[4]],
+            None => padded_op(encoded, i).map_err(|error| DecodeFailure {
+                offset: i,
+                error,
+                pixels_decoded: num_pixels - remaining,
+            })?,
+        };
+        let mut count = 1;
+        match op[0] {
+            0b11111110 => {
+                pixel.r = op[1];
+                pixel.g = op[2];
+                pixel.b = op[3];
+                i += 4;
+            }
+            0b11111111 => {
+                pixel = Pixel {
+                    r: op[1],
+                    g: op[2],
+                    b: op[3],
+                    a: op[4],
+                };
+                i += 5;
+            }
+            tag => {
+                match tag >> 6 {
+                    0b00 => pixel = hash.get(tag & 0b00111111),
+                    0b01 => {
+                        pixel.r = pixel.r.wrapping_add((tag >> 4) & 0b11).wrapping_sub(2);
+                        pixel.g = pixel.g.wrapping_add((tag >> 2) & 0b11).wrapping_sub(2);
+                        pixel.b = pixel.b.wrapping_add(tag & 0b11).wrapping_sub(2);
+                    }
+                    0b10 => {
+                        let dg = (tag & 0b00111111).wrapping_sub(32);
+                        pixel.r = pixel
+                            .r
+                            .wrapping_add(dg)
+                            .wrapping_add(op[1] >> 4)
+                            .wrapping_sub(8);
+                        pixel.g = pixel.g.wrapping_add(dg);
+                        pixel.b = pixel
+                            .b
+                            .wrapping_add(dg)
+                            .wrapping_add(op[1] & 0b1111)
+                            .wrapping_sub(8);
+                        i += 1;
+                    }
+                    _ => count = (tag & 0b00111111) as usize + 1,
+                }
+                i += 1;
+            }
+        }
+        // like the reference decoder, every op updates the hash
+        hash.insert(&pixel);
+        let count = usize::min(count, remaining);
+        put(pixel, count);
+        remaining -= count;
+    }
+
+    if encoded.get(i..i + END_MARKER.len()) != Some(&END_MARKER[..]) {
+        return Err(DecodeFailure {
+            offset: i,
+            error: QoiError::MissingEndMarker,
+            pixels_decoded: num_pixels - remaining,
+        });
+    }
+    Ok(())
+}
+
+/// The op at `encoded[i..]` padded with zeros to 5 bytes, or an error if it is cut off.
+#[cold]
+fn padded_op(encoded: &[u8], i: usize) -> Result<[u8; 5], QoiError> {
+    let rest = encoded.get(i..).unwrap_or_default();
+    let truncated = QoiError::Truncated {
+        offset: encoded.len(),
+    };
+    let size = Chunk::size_from_tag(*rest.first().ok_or(truncated.clone())?);
+    if rest.len() < size {
+        return Err(truncated);
+    }
+    let mut op = [0; 5];
+    op[..rest.len()].copy_from_slice(rest);
+    Ok(op)
+}
@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+
+use crate::{
+    run::RunLength, OpDiff, OpIndex, OpLuma, OpRGB, OpRGBA, OpRun, Pixel, QOIHash, QoiError,
+};
+
+/// Somewhere the encoded bytes can be put.
+pub(crate) trait OpSink {
+    fn put(&mut self, bytes: &[u8]);
+}
+
+impl OpSink for Vec<u8> {
+    fn put(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+/// Writes into a fixed buffer and remembers if it ran out of space.
+pub(crate) struct SliceSink<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<'a> SliceSink<'a> {
+    pub(crate) fn new(buffer: &'a mut [u8]) -> Self {
+        SliceSink {
+            buffer,
+            len: 0,
+            overflowed: false,
+        }
+    }
+
+    /// Returns the number of bytes written.
+    pub(crate) fn finish(self) -> Result<usize, QoiError> {
+        if self.overflowed {
+            return Err(QoiError::BufferTooSmall {
+                len: self.buffer.len(),
+            });
+        }
+        Ok(self.len)
+    }
+}
+
+impl OpSink for SliceSink<'_> {
+    fn put(&mut self, bytes: &[u8]) {
+        if self.overflowed {
+            return;
+        }
+        match self.buffer.get_mut(self.len..self.len + bytes.len()) {
+            Some(target) => {
+                target.copy_from_slice(bytes);
+                self.len += bytes.len();
+            }
+            None => self.overflowed = true,
+        }
+    }
+}
+
+/// Everything that has to be carried over from one pixel to the next while encoding.
+#[derive(Debug, Clone)]
+pub(crate) struct EncoderState {
+    hash: QOIHash,
+    previous: Pixel,
+    run: u8,
+}
+
+impl EncoderState {
+    pub(crate) fn new() -> Self {
+        EncoderState {
+            hash: QOIHash::new(),
+            previous: Pixel::default(),
+            run: 0,
+        }
+    }
+
+    /// Appends the ops for `pixel` to `encoded`.
+    /// Runs are only written once they end, so call [`EncoderState::flush_run`] after the last pixel.
+    pub(crate) fn encode_pixel(&mut self, pixel: Pixel, encoded: &mut impl OpSink) {
+        if pixel == self.previous {
+            self.run += 1;
+            if self.run == 62 {
+                self.flush_run(encoded);
+            }
+            return;
+        }
+        self.flush_run(encoded);
+
+        if let Some(index) = self.hash.lookup(&pixel) {
+            // pixel exists in hash
+            encoded.put(&[OpIndex::new(index).get_encoding()]);
+        } else {
+            self.hash.insert(&pixel);
+            let previous = self.previous;
+            let dr = pixel.r.wrapping_sub(previous.r) as i8;
+            let dg = pixel.g.wrapping_sub(previous.g) as i8;
+            let db = pixel.b.wrapping_sub(previous.b) as i8;
+            if pixel.a != previous.a {
+                // alpha changed, only OpRGBA can encode this
+                encoded.put(&OpRGBA::new(pixel.r, pixel.g, pixel.b, pixel.a).get_encoding());
+            } else if fits_diff(dr, dg, db) {
+                // difference is small enough to be encoded with OpDiff
+                encoded.put(&[OpDiff::new(dr, dg, db).get_encoding()]);
+            } else if fits_luma(dr, dg, db) {
+                // difference is small enough to be encoded with OpLuma
+                encoded.put(&OpLuma::new(dr, dg, db).get_encoding());
+            } else {
+                encoded.put(&OpRGB::new(pixel.r, pixel.g, pixel.b).get_encoding());
+            }
+        }
+        self.previous = pixel;
+    }
+
+    /// Same as calling [`EncoderState::encode_pixel`] for every pixel,
+    /// but finds the end of a run with `run_length` instead of comparing pixel by pixel.
+    pub(crate) fn encode_pixels(
+        &mut self,
+        pixels: &[Pixel],
+        run_length: RunLength,
+        encoded: &mut impl OpSink,
+    ) {
+        let mut i = 0;
+        while i < pixels.len() {
+            let pixel = pixels[i];
+            if pixel != self.previous {
+                self.encode_pixel(pixel, encoded);
+                i += 1;
+                continue;
+            }
+            let len = run_length(&pixels[i..], pixel);
+            i += len;
+            // split up the same way as adding the pixels one at a time
+            let run = self.run as usize + len;
+            for _ in 0..run / 62 {
+                encoded.put(&[OpRun::new(61).get_encoding()]);
+            }
+            self.run = (run % 62) as u8;
+        }
+    }
+
+    /// Writes the pending run, if there is one.
+    pub(crate) fn flush_run(&mut self, encoded: &mut impl OpSink) {
+        if self.run > 0 {
+            // runs are stored with a bias of -1
+            encoded.put(&[OpRun::new(self.run - 1).get_encoding()]);
+            self.run = 0;
+        }
+    }
+}
+
+/// Whether all differences are in -2..2, checked for all channels at once
+/// by biasing them to 0..4 and packing them into one integer.
+pub(crate) fn fits_diff(dr: i8, dg: i8, db: i8) -> bool {
+    let packed = u32::from_le_bytes([
+        dr.wrapping_add(2) as u8,
+        dg.wrapping_add(2) as u8,
+        db.wrapping_add(2) as u8,
+        0,
+    ]);
+    packed & 0x00FC_FCFC == 0
+}
+
+/// Whether the green difference is in -32..32 and the others are within -8..8 of it,
+/// packed the same way as in [`fits_diff`].
+pub(crate) fn fits_luma(dr: i8, dg: i8, db: i8) -> bool {
+    let packed = u32::from_le_bytes([
+        dg.wrapping_add(32) as u8,
+        dr.wrapping_sub(dg).wrapping_add(8) as u8,
+        db.wrapping_sub(dg).wrapping_add(8) as u8,
+        0,
+    ]);
+    packed & 0x00F0_F0C0 == 0
+}
@@ -0,0 +1,90 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QoiError {
+    /// The stream does not start with `qoif`.
+    InvalidMagic([u8; 4]),
+    /// The header declares a channel count other than 3 or 4.
+    InvalidChannels(u8),
+    /// The header declares a color space other than 0 or 1.
+    InvalidColorSpace(u8),
+    /// The stream ends in the middle of the header or of an op.
+    Truncated { offset: usize },
+    /// The stream does not end with seven 0x00 bytes and a 0x01.
+    MissingEndMarker,
+    /// The number of pixels does not match width * height.
+    PixelCountMismatch { expected: usize, actual: usize },
+    /// The number of bytes does not match width * height * bytes per pixel.
+    ByteCountMismatch { expected: usize, actual: usize },
+    /// width * height does not fit into memory.
+    DimensionsTooLarge { width: u32, height: u32 },
+    /// A row stride of `stride` bytes is shorter than a row of `row_len` bytes.
+    InvalidStride { stride: usize, row_len: usize },
+    /// The output buffer of `len` bytes is too small to hold the result.
+    BufferTooSmall { len: usize },
+    /// The header describes an image larger than the [`DecodeLimits`](crate::DecodeLimits) allow.
+    LimitExceeded { width: u32, height: u32 },
+    /// An [`Op`](crate::Op) with a field outside of the range it can be stored in.
+    InvalidOp(crate::Op),
+}
+
+impl fmt::Display for QoiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QoiError::InvalidMagic(magic) => write!(f, "invalid magic: {:?}", magic),
+            QoiError::InvalidChannels(channels) => {
+                write!(f, "invalid number of channels: {}", channels)
+            }
+            QoiError::InvalidColorSpace(color_space) => {
+                write!(f, "invalid color space: {}", color_space)
+            }
+            QoiError::Truncated { offset } => write!(f, "stream is truncated at byte {}", offset),
+            QoiError::MissingEndMarker => write!(f, "missing or malformed end marker"),
+            QoiError::PixelCountMismatch { expected, actual } => {
+                write!(f, "expected {} pixels, but got {}", expected, actual)
+            }
+            QoiError::ByteCountMismatch { expected, actual } => {
+                write!(f, "expected {} bytes, but got {}", expected, actual)
+            }
+            QoiError::DimensionsTooLarge { width, height } => {
+                write!(f, "image dimensions are too large: {}x{}", width, height)
+            }
+            QoiError::InvalidStride { stride, row_len } => write!(
+                f,
+                "stride of {} bytes is shorter than a row of {} bytes",
+                stride, row_len
+            ),
+            QoiError::BufferTooSmall { len } => {
+                write!(f, "output buffer of {} bytes is too small", len)
+            }
+            QoiError::LimitExceeded { width, height } => {
+                write!(
+                    f,
+                    "image of {}x{} pixels exceeds the decode limits",
+                    width, height
+                )
+            }
+            QoiError::InvalidOp(op) => write!(f, "op has a field out of range: {:?}", op),
+        }
+    }
+}
+
+impl core::error::Error for QoiError {}
+
+#[cfg(feature = "std")]
+impl From<QoiError> for io::Error {
+    fn from(err: QoiError) -> Self {
+        let kind = match err {
+            QoiError::PixelCountMismatch { .. }
+            | QoiError::ByteCountMismatch { .. }
+            | QoiError::DimensionsTooLarge { .. }
+            | QoiError::InvalidStride { .. }
+            | QoiError::BufferTooSmall { .. }
+            | QoiError::InvalidOp(_) => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err)
+    }
+}
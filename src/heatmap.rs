@@ -0,0 +1,73 @@
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::{
+    num_encoded_pixels, tokenize, Channels, ColorSpace, DecodeLimits, Op, Pixel, QoiError,
+    QoiHeader, QoiImage,
+};
+
+/// What [`heatmap`] shows for every pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heatmap {
+    /// The op that produced the pixel, in the color of [`Heatmap::op_color`].
+    Ops,
+    /// The bits of the op that produced the pixel, shared between all pixels of a run,
+    /// from black for nothing to white for the 40 bits of an OpRGBA.
+    BitsPerPixel,
+}
+
+impl Heatmap {
+    /// Red for OpRGB, magenta for OpRGBA, green for OpIndex, blue for OpDiff,
+    /// yellow for OpLuma and dark gray for OpRun.
+    pub fn op_color(op: &Op) -> Pixel {
+        let (r, g, b) = match op {
+            Op::RGB { .. } => (255, 0, 0),
+            Op::RGBA { .. } => (255, 0, 255),
+            Op::Index(_) => (0, 200, 0),
+            Op::Diff { .. } => (0, 100, 255),
+            Op::Luma { .. } => (255, 200, 0),
+            Op::Run(_) => (60, 60, 60),
+        };
+        Pixel { r, g, b, a: 255 }
+    }
+
+    fn color(self, op: &Op) -> Pixel {
+        match self {
+            Heatmap::Ops => Heatmap::op_color(op),
+            Heatmap::BitsPerPixel => {
+                // 8 * size / pixels bits, scaled so that 40 of them are 255
+                let gray = (op.size() * 51 / op.pixels()) as u8;
+                Pixel {
+                    r: gray,
+                    g: gray,
+                    b: gray,
+                    a: 255,
+                }
+            }
+        }
+    }
+}
+
+/// Renders which parts of `encoded` take up how much space, as an opaque image of the same size,
+/// with the [default](DecodeLimits::default) limits.
+pub fn heatmap(encoded: &[u8], kind: Heatmap) -> Result<QoiImage, QoiError> {
+    let mut header = QoiHeader::from_u8(encoded)?;
+    let num_pixels = num_encoded_pixels(
+        encoded,
+        &header,
+        DecodeLimits::default(),
+        mem::size_of::<Pixel>(),
+    )?;
+
+    let mut pixels = Vec::with_capacity(num_pixels);
+    for token in tokenize(encoded) {
+        let (_, op) = token?;
+        // a run that goes past the end of the image is cut off
+        let count = usize::min(op.pixels(), num_pixels - pixels.len());
+        pixels.resize(pixels.len() + count, kind.color(&op));
+    }
+
+    header.channels = Channels::RGB;
+    header.color_space = ColorSpace::SRGB;
+    Ok(QoiImage { header, pixels })
+}
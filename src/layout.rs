@@ -0,0 +1,183 @@
+use crate::Pixel;
+
+/// The order and number of bytes a pixel takes up in a byte buffer.
+///
+/// Layouts without alpha are read as fully opaque and drop alpha when written.
+/// The 16 bit layouts are stored in the given byte order, with red in the most significant bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelLayout {
+    RGB,
+    RGBA,
+    BGR,
+    BGRA,
+    ARGB,
+    /// 5 bits red, 6 bits green and 5 bits blue, little endian
+    RGB565LE,
+    /// 5 bits red, 6 bits green and 5 bits blue, big endian
+    RGB565BE,
+    /// 1 unused bit and 5 bits each for red, green and blue, little endian
+    RGB555LE,
+    /// 1 unused bit and 5 bits each for red, green and blue, big endian
+    RGB555BE,
+    /// 8 bit luminance with BT.601 weights, read back as gray
+    Luma,
+}
+
+/// 4x4 Bayer matrix for ordered dithering, with thresholds from 0 to 15.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl PixelLayout {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelLayout::Luma => 1,
+            PixelLayout::RGB565LE
+            | PixelLayout::RGB565BE
+            | PixelLayout::RGB555LE
+            | PixelLayout::RGB555BE => 2,
+            PixelLayout::RGB | PixelLayout::BGR => 3,
+            PixelLayout::RGBA | PixelLayout::BGRA | PixelLayout::ARGB => 4,
+        }
+    }
+
+    /// Reads a pixel from the first [`PixelLayout::bytes_per_pixel`] bytes of `source`.
+    /// Channels with fewer than 8 bits are scaled up to the full range.
+    pub fn read(&self, source: &[u8]) -> Pixel {
+        match self {
+            PixelLayout::RGB => Pixel {
+                r: source[0],
+                g: source[1],
+                b: source[2],
+                a: 255,
+            },
+            PixelLayout::RGBA => Pixel {
+                r: source[0],
+                g: source[1],
+                b: source[2],
+                a: source[3],
+            },
+            PixelLayout::BGR => Pixel {
+                r: source[2],
+                g: source[1],
+                b: source[0],
+                a: 255,
+            },
+            PixelLayout::BGRA => Pixel {
+                r: source[2],
+                g: source[1],
+                b: source[0],
+                a: source[3],
+            },
+            PixelLayout::ARGB => Pixel {
+                r: source[1],
+                g: source[2],
+                b: source[3],
+                a: source[0],
+            },
+            PixelLayout::RGB565LE | PixelLayout::RGB565BE => {
+                let value = self.read_u16(source);
+                Pixel {
+                    r: expand5((value >> 11) as u8),
+                    g: expand6((value >> 5) as u8),
+                    b: expand5(value as u8),
+                    a: 255,
+                }
+            }
+            PixelLayout::RGB555LE | PixelLayout::RGB555BE => {
+                let value = self.read_u16(source);
+                Pixel {
+                    r: expand5((value >> 10) as u8),
+                    g: expand5((value >> 5) as u8),
+                    b: expand5(value as u8),
+                    a: 255,
+                }
+            }
+            PixelLayout::Luma => Pixel {
+                r: source[0],
+                g: source[0],
+                b: source[0],
+                a: 255,
+            },
+        }
+    }
+
+    /// Writes `pixel` into the first [`PixelLayout::bytes_per_pixel`] bytes of `target`.
+    pub fn write(&self, pixel: Pixel, target: &mut [u8]) {
+        match self {
+            PixelLayout::RGB => target[..3].copy_from_slice(&[pixel.r, pixel.g, pixel.b]),
+            PixelLayout::RGBA => target[..4].copy_from_slice(&[pixel.r, pixel.g, pixel.b, pixel.a]),
+            PixelLayout::BGR => target[..3].copy_from_slice(&[pixel.b, pixel.g, pixel.r]),
+            PixelLayout::BGRA => target[..4].copy_from_slice(&[pixel.b, pixel.g, pixel.r, pixel.a]),
+            PixelLayout::ARGB => target[..4].copy_from_slice(&[pixel.a, pixel.r, pixel.g, pixel.b]),
+            PixelLayout::RGB565LE | PixelLayout::RGB565BE => {
+                let value = ((pixel.r as u16 >> 3) << 11)
+                    | ((pixel.g as u16 >> 2) << 5)
+                    | (pixel.b as u16 >> 3);
+                self.write_u16(value, target);
+            }
+            PixelLayout::RGB555LE | PixelLayout::RGB555BE => {
+                let value = ((pixel.r as u16 >> 3) << 10)
+                    | ((pixel.g as u16 >> 3) << 5)
+                    | (pixel.b as u16 >> 3);
+                self.write_u16(value, target);
+            }
+            PixelLayout::Luma => {
+                // BT.601 weights in 8 bit fixed point, they add up to 256
+                let luma =
+                    (77 * pixel.r as u32 + 150 * pixel.g as u32 + 29 * pixel.b as u32 + 128) >> 8;
+                target[0] = luma as u8;
+            }
+        }
+    }
+
+    /// Like [`PixelLayout::write`], but applies ordered dithering for the 16 bit layouts,
+    /// so that gradients do not show bands. `x` and `y` are the position of the pixel in the image.
+    pub fn write_dithered(&self, pixel: Pixel, x: u32, y: u32, target: &mut [u8]) {
+        let threshold = BAYER[y as usize % 4][x as usize % 4];
+        // scale the threshold to the range of the bits that are dropped by the conversion
+        let dither = |value: u8, dropped: u32| value.saturating_add(threshold >> (4 - dropped));
+        let pixel = match self {
+            PixelLayout::RGB565LE | PixelLayout::RGB565BE => Pixel {
+                r: dither(pixel.r, 3),
+                g: dither(pixel.g, 2),
+                b: dither(pixel.b, 3),
+                a: pixel.a,
+            },
+            PixelLayout::RGB555LE | PixelLayout::RGB555BE => Pixel {
+                r: dither(pixel.r, 3),
+                g: dither(pixel.g, 3),
+                b: dither(pixel.b, 3),
+                a: pixel.a,
+            },
+            _ => pixel,
+        };
+        self.write(pixel, target);
+    }
+
+    fn read_u16(&self, source: &[u8]) -> u16 {
+        let bytes = [source[0], source[1]];
+        match self {
+            PixelLayout::RGB565BE | PixelLayout::RGB555BE => u16::from_be_bytes(bytes),
+            _ => u16::from_le_bytes(bytes),
+        }
+    }
+
+    fn write_u16(&self, value: u16, target: &mut [u8]) {
+        let bytes = match self {
+            PixelLayout::RGB565BE | PixelLayout::RGB555BE => value.to_be_bytes(),
+            _ => value.to_le_bytes(),
+        };
+        target[..2].copy_from_slice(&bytes);
+    }
+}
+
+/// Scales a 5 bit channel to 8 bits, so that 31 becomes 255.
+fn expand5(value: u8) -> u8 {
+    let value = value & 0b11111;
+    (value << 3) | (value >> 2)
+}
+
+/// Scales a 6 bit channel to 8 bits, so that 63 becomes 255.
+fn expand6(value: u8) -> u8 {
+    let value = value & 0b111111;
+    (value << 2) | (value >> 4)
+}
@@ -0,0 +1,56 @@
+use alloc::vec;
+
+use crate::{decoder::decode_ops_at, DecodeLimits, Pixel, QoiError, QoiHeader, QoiImage};
+
+/// Where and why [`decode_lenient`] stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeFailure {
+    /// The byte at which the op or end marker that could not be read starts.
+    pub offset: usize,
+    pub error: QoiError,
+    /// How many pixels were decoded before, in row-major order.
+    pub pixels_decoded: usize,
+}
+
+/// An image that was decoded as far as possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialImage {
+    /// Pixels after the failure are set to the fill color.
+    pub image: QoiImage,
+    /// `None` if the whole stream was valid.
+    pub failure: Option<DecodeFailure>,
+}
+
+/// Decodes as much of `encoded` as possible, for example of a file that was cut off,
+/// with the [`LENIENT`](DecodeLimits::LENIENT) limits.
+///
+/// Only an invalid header or an image exceeding the limits are errors, as nothing can be decoded then.
+/// Everything after the point where decoding failed is set to `fill`.
+pub fn decode_lenient(encoded: &[u8], fill: Pixel) -> Result<PartialImage, QoiError> {
+    decode_lenient_with_limits(encoded, fill, DecodeLimits::LENIENT)
+}
+
+/// Like [`decode_lenient`], but with the given `limits`.
+///
+/// Unlike the other decoders, the whole image is allocated even if the stream is too short for it,
+/// so untrusted input needs reasonable limits.
+pub fn decode_lenient_with_limits(
+    encoded: &[u8],
+    fill: Pixel,
+    limits: DecodeLimits,
+) -> Result<PartialImage, QoiError> {
+    let header = QoiHeader::from_u8(encoded)?;
+    let num_pixels = limits.check(&header, core::mem::size_of::<Pixel>())?;
+
+    let mut pixels = vec![fill; num_pixels];
+    let mut position = 0;
+    let result = decode_ops_at(encoded, num_pixels, |pixel, count| {
+        pixels[position..position + count].fill(pixel);
+        position += count;
+    });
+
+    Ok(PartialImage {
+        image: QoiImage { header, pixels },
+        failure: result.err(),
+    })
+}
@@ -1,11 +1,53 @@
-#![feature(mixed_integer_ops)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![allow(clippy::upper_case_acronyms)]
 
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+use core::mem;
+
+mod asm;
+pub use asm::{assemble, disassemble, AsmError};
+mod decoder;
+use decoder::decode_ops;
+mod encoder;
+use encoder::{EncoderState, OpSink, SliceSink};
+mod error;
+pub use error::QoiError;
+mod heatmap;
+pub use heatmap::{heatmap, Heatmap};
+mod layout;
+pub use layout::PixelLayout;
+mod lenient;
+pub use lenient::{decode_lenient, decode_lenient_with_limits, DecodeFailure, PartialImage};
+mod limits;
+pub use limits::DecodeLimits;
+mod op;
+pub use op::{tokenize, Op, Tokens};
+mod run;
+mod sink;
+pub use sink::{decode_to_sink, PixelSink, SinkDecoder};
+mod stats;
+pub use stats::{analyze, encode_with_stats, EncodeStats, OpStats};
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+pub use stream::{Decoder, Encoder};
 mod structs;
 pub use structs::*;
+mod validate;
+pub use validate::{validate, ValidationReport, Warning, WarningKind};
 
 mod tests;
 
-fn header(width: u32, height: u32, channels: Channels, colorspace: ColorSpace) -> [u8; 14] {
+/// Every QOI stream ends with seven 0x00 bytes followed by a single 0x01.
+pub(crate) const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+pub(crate) fn header(
+    width: u32,
+    height: u32,
+    channels: Channels,
+    colorspace: ColorSpace,
+) -> [u8; 14] {
     let mut header = [0; 14];
     header[0] = "qoif".as_bytes()[0];
     header[1] = "qoif".as_bytes()[1];
@@ -24,184 +66,455 @@ fn header(width: u32, height: u32, channels: Channels, colorspace: ColorSpace) -
     header
 }
 
-pub fn encode_from_u8(bytes: &[u8], width: u32, height: u32) -> Vec<u8> {
-    let pixels = bytes
-        .chunks(4)
-        .map(|chunk| {
-            let mut pixel = Pixel::default();
-            pixel.r = chunk[0];
-            pixel.g = chunk[1];
-            pixel.b = chunk[2];
-            pixel
-        })
-        .collect::<Vec<_>>();
-    encode_from_pix(&pixels, width, height)
+/// width * height, if it fits into a `usize`.
+fn num_pixels(width: u32, height: u32) -> Result<usize, QoiError> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .ok_or(QoiError::DimensionsTooLarge { width, height })
+}
+
+/// Checks `header` against `limits` and that `encoded` is long enough to hold that many pixels.
+/// No op produces more than 62 pixels per byte, so a short stream with a huge header
+/// is rejected before anything is allocated for it.
+fn num_encoded_pixels(
+    encoded: &[u8],
+    header: &QoiHeader,
+    limits: DecodeLimits,
+    bytes_per_pixel: usize,
+) -> Result<usize, QoiError> {
+    let num_pixels = limits.check(header, bytes_per_pixel)?;
+    let ops = encoded.len().saturating_sub(14 + END_MARKER.len());
+    if ops.saturating_mul(62) < num_pixels {
+        return Err(QoiError::Truncated {
+            offset: encoded.len(),
+        });
+    }
+    Ok(num_pixels)
 }
 
+/// Encodes `bytes` with pixels in the given `layout`, panicking if they do not match `width` and `height`.
+pub fn encode_from_u8(bytes: &[u8], width: u32, height: u32, layout: PixelLayout) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    layout_pixels(bytes, width, height, layout)
+        .and_then(|pixels| encode_to(pixels, width, height, &mut encoded))
+        .unwrap_or_else(|err| panic!("{}", err));
+    encoded
+}
+
+/// Encodes `pixels`, panicking if they do not match `width` and `height`.
+/// See [`try_encode`] for a fallible version.
 pub fn encode_from_pix(pixels: &[Pixel], width: u32, height: u32) -> Vec<u8> {
-    let mut hash = QOIHash::new();
-    let mut encoded = Vec::from(header(width, height, Channels::RGB, ColorSpace::SRGB));
-    let mut previous = Pixel::default();
+    try_encode(pixels, width, height).unwrap_or_else(|err| panic!("{}", err))
+}
 
-    let num_pixels = pixels.len();
-    assert_eq!(num_pixels, width as usize * height as usize);
+pub fn try_encode(pixels: &[Pixel], width: u32, height: u32) -> Result<Vec<u8>, QoiError> {
+    let mut encoded = Vec::new();
+    encode_slice_to(pixels, width, height, &mut encoded)?;
+    Ok(encoded)
+}
 
-    let mut i = 0;
-    loop {
-        if i >= num_pixels {
-            break;
-        }
-        let pixel = pixels[i];
-        let index = hash.lookup(&pixel);
-        if previous == pixel {
-            // start run of same color
-            let mut num_same = 0; // start with offset, as spec
-            loop {
-                if num_same == 61 {
-                    break;
-                }
-                if i + num_same + 1 < num_pixels && pixels[i + num_same + 1] == pixel {
-                    num_same += 1;
-                } else {
-                    break;
-                }
-            }
-            i = i + num_same + 1;
-            encoded.push(OpRun::new(num_same as u8).get_encoding());
-        } else if index.is_some() {
-            // pixel exists in hash
-            previous = pixel;
-            encoded.push(OpIndex::new(index.unwrap() as u8).get_encoding());
-            i += 1;
-        } else {
-            hash.insert(&pixel);
-            let dr = unsafe { std::mem::transmute::<_, i8>(pixel.r.wrapping_sub(previous.r)) };
-            let dg = unsafe { std::mem::transmute::<_, i8>(pixel.g.wrapping_sub(previous.g)) };
-            let db = unsafe { std::mem::transmute::<_, i8>(pixel.b.wrapping_sub(previous.b)) };
-            if dr >= -2 && dr < 2 && dg >= -2 && dg < 2 && db >= -2 && db < 2 {
-                // difference is small enough to be encoded with OpDiff
-                previous = pixel;
-                encoded.push(OpDiff::new(dr, dg, db).get_encoding());
-            } else if dg >= -32
-                && dg < 32
-                && dr.wrapping_sub(dg) >= -8
-                && dr.wrapping_sub(dg) < 8
-                && db.wrapping_sub(dg) >= -8
-                && db.wrapping_sub(dg) < 8
-            {
-                // difference is small enough to be encoded with OpLuma
-                previous = pixel;
-                encoded.extend_from_slice(&OpLuma::new(dr, dg, db).get_encoding());
-            } else {
-                let pix_enc = OpRGB::new(pixel.r, pixel.g, pixel.b).get_encoding();
-                previous = pixel;
-                encoded.extend_from_slice(&pix_enc);
-            }
-            i += 1;
-        }
-    }
+/// [`encode_from_pix`] without the SIMD run detection, to compare against in benchmarks.
+#[cfg(any(test, feature = "bench"))]
+pub fn encode_from_pix_scalar(pixels: &[Pixel], width: u32, height: u32) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    encode_to(pixels.iter().copied(), width, height, &mut encoded)
+        .unwrap_or_else(|err| panic!("{}", err));
     encoded
 }
 
-pub fn decode_to_u8(encoded: &[u8]) -> Vec<u8> {
-    let pixels = decode_to_pix(&encoded);
-    let decoded = pixels
-        .into_iter()
-        .flat_map(|pixel| {
-            let mut rgb = [0; 4];
-            rgb[0] = pixel.r;
-            rgb[1] = pixel.g;
-            rgb[2] = pixel.b;
-            rgb[3] = pixel.a;
-            rgb
-        })
-        .collect::<Vec<_>>();
+/// The largest number of bytes an image of this size can take up when encoded,
+/// or `None` if that does not fit into a `usize`.
+pub fn max_encoded_len(width: u32, height: u32) -> Option<usize> {
+    // every pixel is an OpRGBA in the worst case, no matter which channels end up in the header
+    (width as usize)
+        .checked_mul(height as usize)?
+        .checked_mul(Channels::RGBA as usize + 1)?
+        .checked_add(14 + END_MARKER.len())
+}
+
+/// Encodes `pixels` into `buffer` without allocating and returns the number of bytes written.
+/// A buffer of [`max_encoded_len`] bytes is always large enough.
+pub fn encode_into(
+    pixels: &[Pixel],
+    width: u32,
+    height: u32,
+    buffer: &mut [u8],
+) -> Result<usize, QoiError> {
+    let mut encoded = SliceSink::new(buffer);
+    encode_slice_to(pixels, width, height, &mut encoded)?;
+    encoded.finish()
+}
+
+/// Like [`encode_into`], but takes bytes like [`encode_from_u8`].
+pub fn encode_from_u8_into(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+    buffer: &mut [u8],
+) -> Result<usize, QoiError> {
+    let mut encoded = SliceSink::new(buffer);
+    encode_to(
+        layout_pixels(bytes, width, height, layout)?,
+        width,
+        height,
+        &mut encoded,
+    )?;
+    encoded.finish()
+}
+
+fn layout_pixels(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    layout: PixelLayout,
+) -> Result<impl ExactSizeIterator<Item = Pixel> + Clone + '_, QoiError> {
+    let bytes_per_pixel = layout.bytes_per_pixel();
+    let expected = num_pixels(width, height)?
+        .checked_mul(bytes_per_pixel)
+        .ok_or(QoiError::DimensionsTooLarge { width, height })?;
+    if bytes.len() != expected {
+        return Err(QoiError::ByteCountMismatch {
+            expected,
+            actual: bytes.len(),
+        });
+    }
+    Ok(bytes
+        .chunks_exact(bytes_per_pixel)
+        .map(move |chunk| layout.read(chunk)))
+}
+
+fn encode_to(
+    pixels: impl ExactSizeIterator<Item = Pixel> + Clone,
+    width: u32,
+    height: u32,
+    encoded: &mut impl OpSink,
+) -> Result<(), QoiError> {
+    encode_header(pixels.clone(), width, height, encoded)?;
+    encode_ops(pixels, encoded);
+    Ok(())
+}
+
+/// Writes the ops for all `pixels` and the end marker.
+fn encode_ops(pixels: impl Iterator<Item = Pixel>, encoded: &mut impl OpSink) {
+    let mut state = EncoderState::new();
+    for pixel in pixels {
+        state.encode_pixel(pixel, encoded);
+    }
+    state.flush_run(encoded);
+    encoded.put(&END_MARKER);
+}
+
+/// Like [`encode_to`], but skips over runs with the fastest run detection the CPU supports.
+fn encode_slice_to(
+    pixels: &[Pixel],
+    width: u32,
+    height: u32,
+    encoded: &mut impl OpSink,
+) -> Result<(), QoiError> {
+    encode_header(pixels.iter().copied(), width, height, encoded)?;
+    encode_slice_ops(pixels, encoded);
+    Ok(())
+}
+
+/// Like [`encode_ops`], but for a slice, see [`encode_slice_to`].
+fn encode_slice_ops(pixels: &[Pixel], encoded: &mut impl OpSink) {
+    let mut state = EncoderState::new();
+    state.encode_pixels(pixels, run::select(), encoded);
+    state.flush_run(encoded);
+    encoded.put(&END_MARKER);
+}
+
+/// Encodes like `qoi_encode` from the reference implementation, so the output is the same byte for byte.
+///
+/// `bytes` holds RGB or RGBA pixels depending on `channels`, which is written to the header
+/// together with `color_space` instead of being picked based on the pixels.
+/// Unlike the reference, empty images are allowed.
+pub fn encode_reference(
+    bytes: &[u8],
+    width: u32,
+    height: u32,
+    channels: Channels,
+    color_space: ColorSpace,
+) -> Result<Vec<u8>, QoiError> {
+    let layout = match channels {
+        Channels::RGB => PixelLayout::RGB,
+        Channels::RGBA => PixelLayout::RGBA,
+    };
+    let pixels = layout_pixels(bytes, width, height, layout)?;
+    let mut encoded = Vec::new();
+    encoded.put(&header(width, height, channels, color_space));
+    encode_ops(pixels, &mut encoded);
+    Ok(encoded)
+}
+
+/// Re-encodes `encoded` with the encoder in this crate, panicking if it is not a valid QOI stream.
+/// See [`try_recompress`] for a fallible version.
+pub fn recompress(encoded: &[u8]) -> Vec<u8> {
+    try_recompress(encoded).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Decodes `encoded` with the [default](DecodeLimits::default) limits and encodes it again,
+/// to shrink streams written by other encoders.
+///
+/// The channels and color space of the header are kept as they are.
+/// If the result is not smaller, a copy of `encoded` is returned instead.
+pub fn try_recompress(encoded: &[u8]) -> Result<Vec<u8>, QoiError> {
+    let image = try_decode(encoded)?;
+    let header = &image.header;
+    let mut recompressed = Vec::new();
+    recompressed.put(&crate::header(
+        header.width,
+        header.height,
+        header.channels,
+        header.color_space,
+    ));
+    encode_slice_ops(&image.pixels, &mut recompressed);
+    if recompressed.len() >= encoded.len() {
+        return Ok(encoded.to_vec());
+    }
+    Ok(recompressed)
+}
+
+/// Checks the number of pixels and writes the header.
+fn encode_header(
+    pixels: impl ExactSizeIterator<Item = Pixel>,
+    width: u32,
+    height: u32,
+    encoded: &mut impl OpSink,
+) -> Result<(), QoiError> {
+    let expected = num_pixels(width, height)?;
+    if pixels.len() != expected {
+        return Err(QoiError::PixelCountMismatch {
+            expected,
+            actual: pixels.len(),
+        });
+    }
+
+    let channels = if pixels.into_iter().any(|pixel| pixel.a != 255) {
+        Channels::RGBA
+    } else {
+        Channels::RGB
+    };
+    encoded.put(&header(width, height, channels, ColorSpace::SRGB));
+    Ok(())
+}
+
+/// Decodes `encoded` into tightly packed bytes in the given `layout`,
+/// panicking if it is not a valid QOI stream or exceeds the [default](DecodeLimits::default) limits.
+pub fn decode_to_u8(encoded: &[u8], layout: PixelLayout) -> Vec<u8> {
+    let header = QoiHeader::from_u8(encoded).unwrap_or_else(|err| panic!("{}", err));
+    let bytes_per_pixel = layout.bytes_per_pixel();
+    let num_pixels = num_encoded_pixels(encoded, &header, DecodeLimits::default(), bytes_per_pixel)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let mut decoded = vec![0; num_pixels * bytes_per_pixel];
+    let width = header.width as usize;
+    // the buffer is sized for the header, so none of the checks of decode_into are needed
+    decode_rows(
+        encoded,
+        num_pixels,
+        &mut decoded,
+        width,
+        width * bytes_per_pixel,
+        layout,
+        false,
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
     decoded
 }
 
+/// Decodes `encoded` and returns only the pixels, panicking if it is not a valid QOI stream.
+/// See [`try_decode`] for a fallible version.
 pub fn decode_to_pix(encoded: &[u8]) -> Vec<Pixel> {
-    let mut decoded = Vec::new();
-    let mut hash = QOIHash::new();
-    let mut previous = Pixel::default();
+    decode(encoded).pixels
+}
 
-    // let (width, height, channels, colorspace) = get_header(&encoded[0..14]);
-    // println!("{:?}", (width, height, channels, colorspace));
-    let encoded = &encoded[14..];
+/// Decodes `encoded`, panicking if it is not a valid QOI stream.
+/// See [`try_decode`] for a fallible version.
+pub fn decode(encoded: &[u8]) -> QoiImage {
+    try_decode(encoded).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Decodes `encoded` with the [default](DecodeLimits::default) limits.
+pub fn try_decode(encoded: &[u8]) -> Result<QoiImage, QoiError> {
+    try_decode_with_limits(encoded, DecodeLimits::default())
+}
 
-    // dbg!(&encoded);
+/// Like [`try_decode`], but rejects images larger than `limits` before allocating anything.
+pub fn try_decode_with_limits(encoded: &[u8], limits: DecodeLimits) -> Result<QoiImage, QoiError> {
+    let header = QoiHeader::from_u8(encoded)?;
+    let num_pixels = num_encoded_pixels(encoded, &header, limits, mem::size_of::<Pixel>())?;
 
-    let mut i = 0; // which byte in encoded
-    loop {
-        if i >= encoded.len() {
-            break;
+    let mut decoded = vec![Pixel::default(); num_pixels];
+    let mut position = 0;
+    decode_ops(encoded, num_pixels, |pixel, count| {
+        if count == 1 {
+            decoded[position] = pixel;
+        } else {
+            decoded[position..position + count].fill(pixel);
         }
-        let op = Chunk::from_encoding(&encoded[i..usize::min(i + 4, encoded.len())]);
-        // dbg!(&op);
-        match op {
-            Chunk::RGB(rgb) => {
-                let pixel = Pixel {
-                    r: rgb.r,
-                    g: rgb.g,
-                    b: rgb.b,
-                    a: 255,
-                };
-                hash.insert(&pixel);
-                previous = pixel;
-                decoded.push(pixel);
-                i += 4;
-            }
-            Chunk::RGBA(rgba) => {
-                let pixel = Pixel {
-                    r: rgba.r,
-                    g: rgba.g,
-                    b: rgba.b,
-                    a: rgba.a,
-                };
-                hash.insert(&pixel);
-                previous = pixel;
-                decoded.push(pixel);
-                i += 5;
-            }
-            Chunk::Index(index) => {
-                let pixel = hash.get(index.index);
-                // hash does not need to be updated
-                previous = pixel;
-                decoded.push(pixel);
-                i += 1;
-            }
-            Chunk::Diff(diff) => {
-                let diff = diff.get_diffs();
-                let pixel = Pixel {
-                    r: previous.r.overflowing_add_signed(diff.0).0,
-                    g: previous.g.overflowing_add_signed(diff.1).0,
-                    b: previous.b.overflowing_add_signed(diff.2).0,
-                    a: previous.a,
-                };
-                hash.insert(&pixel);
-                previous = pixel;
-                decoded.push(pixel);
-                i += 1;
-            }
-            Chunk::Luma(luma) => {
-                let diff = luma.get_diffs();
-                let pixel = Pixel {
-                    r: previous.r.overflowing_add_signed(diff.0).0,
-                    g: previous.g.overflowing_add_signed(diff.1).0,
-                    b: previous.b.overflowing_add_signed(diff.2).0,
-                    a: previous.a,
-                };
-                hash.insert(&pixel);
-                previous = pixel;
-                decoded.push(pixel);
-                i += 2;
-            }
-            Chunk::Run(run) => {
-                for _ in 0..=run.run {
-                    decoded.push(previous);
+        position += count;
+    })?;
+
+    Ok(QoiImage {
+        header,
+        pixels: decoded,
+    })
+}
+
+/// Decodes `encoded` straight into `buffer` and returns the header.
+///
+/// Row `y` starts at byte `y * stride`, so `stride` may be larger than `width * bytes_per_pixel`
+/// to decode into a part of a larger canvas.
+/// Bytes between the end of one row and the start of the next one are left untouched.
+pub fn decode_into(
+    encoded: &[u8],
+    buffer: &mut [u8],
+    stride: usize,
+    layout: PixelLayout,
+) -> Result<QoiHeader, QoiError> {
+    decode_into_with(encoded, buffer, stride, layout, false)
+}
+
+/// Like [`decode_into`], but with ordered dithering when converting to a 16 bit layout.
+/// Other layouts are written exactly as with [`decode_into`].
+pub fn decode_into_dithered(
+    encoded: &[u8],
+    buffer: &mut [u8],
+    stride: usize,
+    layout: PixelLayout,
+) -> Result<QoiHeader, QoiError> {
+    decode_into_with(encoded, buffer, stride, layout, true)
+}
+
+fn decode_into_with(
+    encoded: &[u8],
+    buffer: &mut [u8],
+    stride: usize,
+    layout: PixelLayout,
+    dither: bool,
+) -> Result<QoiHeader, QoiError> {
+    let header = QoiHeader::from_u8(encoded)?;
+    let num_pixels = num_pixels(header.width, header.height)?;
+    let too_large = QoiError::DimensionsTooLarge {
+        width: header.width,
+        height: header.height,
+    };
+
+    let width = header.width as usize;
+    let bytes_per_pixel = layout.bytes_per_pixel();
+    let row_len = width
+        .checked_mul(bytes_per_pixel)
+        .ok_or(too_large.clone())?;
+    if stride < row_len {
+        return Err(QoiError::InvalidStride { stride, row_len });
+    }
+    // the last row does not need to be padded to the full stride
+    let needed = match header.height {
+        0 => 0,
+        height => (height as usize - 1)
+            .checked_mul(stride)
+            .and_then(|len| len.checked_add(row_len))
+            .ok_or(too_large)?,
+    };
+    if buffer.len() < needed {
+        return Err(QoiError::BufferTooSmall { len: buffer.len() });
+    }
+
+    decode_rows(encoded, num_pixels, buffer, width, stride, layout, dither)?;
+    Ok(header)
+}
+
+/// Decodes the `num_pixels` pixels of `encoded` into rows of `width` pixels in `buffer`,
+/// which has to be large enough for them.
+fn decode_rows(
+    encoded: &[u8],
+    num_pixels: usize,
+    buffer: &mut [u8],
+    width: usize,
+    stride: usize,
+    layout: PixelLayout,
+    dither: bool,
+) -> Result<(), QoiError> {
+    let bytes_per_pixel = layout.bytes_per_pixel();
+    macro_rules! packed {
+        ($pack:expr) => {
+            decode_packed(encoded, num_pixels, buffer, width, stride, $pack)?
+        };
+    }
+    match (dither, layout, bytes_per_pixel) {
+        // the common layouts without matching on the layout in PixelLayout::write for every op
+        (false, PixelLayout::RGBA, _) => packed!(|p| [p.r, p.g, p.b, p.a]),
+        (false, PixelLayout::RGB, _) => packed!(|p| [p.r, p.g, p.b]),
+        (false, _, 1) => packed!(pack::<1>(layout)),
+        (false, _, 2) => packed!(pack::<2>(layout)),
+        (false, _, 3) => packed!(pack::<3>(layout)),
+        (false, _, 4) => packed!(pack::<4>(layout)),
+        _ => {
+            let (mut x, mut y) = (0, 0);
+            decode_ops(encoded, num_pixels, |pixel, count| {
+                for _ in 0..count {
+                    let start = y * stride + x * bytes_per_pixel;
+                    let target = &mut buffer[start..start + bytes_per_pixel];
+                    layout.write_dithered(pixel, x as u32, y as u32, target);
+                    x += 1;
+                    if x == width {
+                        x = 0;
+                        y += 1;
+                    }
                 }
-                i += 1;
-            }
+            })?
         }
     }
-    decoded
+    Ok(())
+}
+
+/// Converts a pixel to `N` bytes in `layout`.
+fn pack<const N: usize>(layout: PixelLayout) -> impl Fn(Pixel) -> [u8; N] {
+    move |pixel| {
+        let mut packed = [0; N];
+        layout.write(pixel, &mut packed);
+        packed
+    }
+}
+
+/// Decodes into `buffer` with `N` bytes per pixel, converted with `pack`.
+/// Every pixel is only converted once, no matter how long its run is.
+fn decode_packed<const N: usize>(
+    encoded: &[u8],
+    num_pixels: usize,
+    buffer: &mut [u8],
+    width: usize,
+    stride: usize,
+    pack: impl Fn(Pixel) -> [u8; N],
+) -> Result<(), QoiError> {
+    if stride == width * N {
+        // without padding between rows, the pixels can be written one after another
+        let mut start = 0;
+        return decode_ops(encoded, num_pixels, |pixel, count| {
+            let packed = pack(pixel);
+            if count == 1 {
+                buffer[start..start + N].copy_from_slice(&packed);
+            } else {
+                for target in buffer[start..start + count * N].chunks_exact_mut(N) {
+                    target.copy_from_slice(&packed);
+                }
+            }
+            start += count * N;
+        });
+    }
+    let (mut x, mut row_start, mut start) = (0, 0, 0);
+    decode_ops(encoded, num_pixels, |pixel, count| {
+        let packed = pack(pixel);
+        for _ in 0..count {
+            buffer[start..start + N].copy_from_slice(&packed);
+            start += N;
+            x += 1;
+            if x == width {
+                x = 0;
+                row_start += stride;
+                start = row_start;
+            }
+        }
+    })
 }
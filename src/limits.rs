@@ -0,0 +1,70 @@
+use crate::{QoiError, QoiHeader};
+
+/// Upper bounds for the images a decoder accepts.
+///
+/// They are checked against the header before anything is allocated or decoded,
+/// so a tiny stream that claims a huge image is rejected with [`QoiError::LimitExceeded`].
+/// The default only allows the 400 million pixels of the specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub max_width: u32,
+    pub max_height: u32,
+    /// width * height
+    pub max_pixels: usize,
+    /// The most memory the decoder may allocate for the decoded image.
+    /// Only applies to decoders that allocate it themselves.
+    pub max_alloc_bytes: usize,
+}
+
+impl DecodeLimits {
+    /// The largest number of pixels the specification allows.
+    pub const SPEC_MAX_PIXELS: usize = 400_000_000;
+
+    /// No limits at all, apart from what fits into memory.
+    pub const NONE: DecodeLimits = DecodeLimits {
+        max_width: u32::MAX,
+        max_height: u32::MAX,
+        max_pixels: usize::MAX,
+        max_alloc_bytes: usize::MAX,
+    };
+
+    /// The [default](DecodeLimits::default) limits with at most 256 MiB for the decoded image,
+    /// which is enough for 8K. Used by [`decode_lenient`](crate::decode_lenient),
+    /// which allocates the whole image even for a stream that is far too short for it.
+    pub const LENIENT: DecodeLimits = DecodeLimits {
+        max_pixels: DecodeLimits::SPEC_MAX_PIXELS,
+        max_alloc_bytes: 256 << 20,
+        ..DecodeLimits::NONE
+    };
+
+    /// Checks `header` against the limits and returns the number of pixels.
+    /// `bytes_per_pixel` is how much memory the decoder allocates for every pixel.
+    pub(crate) fn check(
+        &self,
+        header: &QoiHeader,
+        bytes_per_pixel: usize,
+    ) -> Result<usize, QoiError> {
+        let (width, height) = (header.width, header.height);
+        let exceeded = QoiError::LimitExceeded { width, height };
+        let num_pixels = (width as usize)
+            .checked_mul(height as usize)
+            .ok_or(exceeded.clone())?;
+        if width > self.max_width
+            || height > self.max_height
+            || num_pixels > self.max_pixels
+            || num_pixels.saturating_mul(bytes_per_pixel) > self.max_alloc_bytes
+        {
+            return Err(exceeded);
+        }
+        Ok(num_pixels)
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_pixels: DecodeLimits::SPEC_MAX_PIXELS,
+            ..DecodeLimits::NONE
+        }
+    }
+}
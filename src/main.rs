@@ -1,4 +1,7 @@
-use qoi::{decode_to_u8, encode_from_u8, QoiHeader};
+use qoi::{
+    analyze, assemble, decode, disassemble, encode_from_u8, heatmap, try_recompress, validate,
+    Channels, EncodeStats, Heatmap, PixelLayout, QoiHeader,
+};
 use std::{
     fs::File,
     io::{BufWriter, Write},
@@ -6,81 +9,188 @@ use std::{
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
-struct Opt {
-    #[structopt()]
-    input: String,
+enum Opt {
+    /// Converts between png and qoi, picked by the extension of the output
+    Convert {
+        #[structopt()]
+        input: String,
+
+        #[structopt()]
+        output: String,
+    },
+    /// Writes the ops of a qoi file as text, one per line
+    Disasm {
+        #[structopt()]
+        input: String,
+
+        /// Defaults to stdout
+        #[structopt()]
+        output: Option<String>,
+    },
+    /// Turns text in the format written by disasm into a qoi file
+    Asm {
+        #[structopt()]
+        input: String,
+
+        #[structopt()]
+        output: String,
+    },
+    /// Checks qoi files for errors and for anything an encoder should not write,
+    /// fails if any of them does not follow the spec
+    Validate {
+        #[structopt(required = true)]
+        inputs: Vec<String>,
+    },
+    /// Re-encodes a qoi file written by another encoder, keeping it as is if that is not smaller
+    Optimize {
+        #[structopt()]
+        input: String,
+
+        /// Defaults to overwriting the input
+        #[structopt()]
+        output: Option<String>,
+    },
+    /// Shows which ops a qoi file is made of, a png is encoded first
+    Stats {
+        #[structopt()]
+        input: String,
+    },
+    /// Renders which op produced each pixel of a qoi file into a png
+    Heatmap {
+        #[structopt()]
+        input: String,
 
-    #[structopt()]
-    output: String,
+        #[structopt()]
+        output: String,
+
+        /// Shows the bits spent on each pixel in grayscale instead
+        #[structopt(long)]
+        bits_per_pixel: bool,
+    },
 }
 
+const SUBCOMMANDS: [&str; 8] = [
+    "convert", "disasm", "asm", "validate", "optimize", "stats", "heatmap", "help",
+];
+
 fn main() {
-    let opt = Opt::from_args();
-    println!("{:?}", opt);
-
-    let file = File::open(&opt.input).expect("invalid input file");
-
-    let (bytes, width, height) = {
-        let png_decoder = png::Decoder::new(file);
-        if let Ok(mut reader) = png_decoder.read_info() {
-            println!("decoding png");
-            // Allocate the output buffer.
-            let mut buf = vec![0; reader.output_buffer_size()];
-            // Read the next frame. An APNG might contain multiple frames.
-            let info = reader.next_frame(&mut buf).unwrap();
-            // Grab the bytes of the image.
-            println!("{:?}", info);
-            assert_eq!(info.bit_depth, png::BitDepth::Eight, "png bit depth must be 8");
-            match info.color_type {
-                png::ColorType::Grayscale => todo!(),
-                png::ColorType::Rgb => {
-                    let mut bytes = Vec::with_capacity((info.width * info.height * 4) as usize);
-                    for row in buf.chunks(info.width as usize * 3) {
-                        for pixel in row.chunks(3) {
-                            bytes.push(pixel[0]);
-                            bytes.push(pixel[1]);
-                            bytes.push(pixel[2]);
-                            bytes.push(255);
-                        }
+    let mut args = std::env::args().collect::<Vec<_>>();
+    // `qoi <input> <output>` converts like it did before there were subcommands
+    if let Some(first) = args.get(1) {
+        if !first.starts_with('-') && !SUBCOMMANDS.contains(&first.as_str()) {
+            args.insert(1, "convert".to_string());
+        }
+    }
+
+    match Opt::from_iter(args) {
+        Opt::Convert { input, output } => convert(&input, &output),
+        Opt::Disasm { input, output } => {
+            let encoded = std::fs::read(&input).expect("invalid input file");
+            let text = disassemble(&encoded);
+            match output {
+                Some(output) => std::fs::write(output, text).unwrap(),
+                None => print!("{}", text),
+            }
+        }
+        Opt::Asm { input, output } => {
+            let text = std::fs::read_to_string(&input).expect("invalid input file");
+            let encoded = assemble(&text).unwrap_or_else(|err| panic!("{}: {}", input, err));
+            std::fs::write(output, encoded).unwrap();
+        }
+        Opt::Validate { inputs } => {
+            let mut all_valid = true;
+            for input in inputs {
+                let encoded = std::fs::read(&input).expect("invalid input file");
+                let report = validate(&encoded);
+                match &report.error {
+                    Some(failure) => {
+                        println!("{}: byte {}: {}", input, failure.offset, failure.error);
+                        all_valid = false;
                     }
-                    (bytes, info.width, info.height)
+                    None => println!("{}: valid, {} warnings", input, report.warnings.len()),
+                }
+                for warning in &report.warnings {
+                    println!("  {}", warning);
                 }
-                png::ColorType::Indexed => todo!(),
-                png::ColorType::GrayscaleAlpha => todo!(),
-                png::ColorType::Rgba => (buf, info.width, info.height),
             }
-        } else {
-            println!("decoding qoi");
-            let bytes = std::fs::read(&opt.input).unwrap();
-            let header = QoiHeader::from_u8(&bytes).expect("invalid qoi header");
-            (decode_to_u8(&bytes), header.width, header.height)
+            if !all_valid {
+                std::process::exit(1);
+            }
         }
-    };
+        Opt::Optimize { input, output } => {
+            let encoded = std::fs::read(&input).expect("invalid input file");
+            let recompressed =
+                try_recompress(&encoded).unwrap_or_else(|err| panic!("{}: {}", input, err));
+            println!(
+                "{}: {} -> {} bytes, saved {}",
+                input,
+                encoded.len(),
+                recompressed.len(),
+                encoded.len() - recompressed.len()
+            );
+            std::fs::write(output.as_ref().unwrap_or(&input), recompressed).unwrap();
+        }
+        Opt::Stats { input } => {
+            let file = std::fs::read(&input).expect("invalid input file");
+            let encoded = if QoiHeader::from_u8(&file).is_ok() {
+                file
+            } else {
+                println!("{} bytes as png", file.len());
+                let (bytes, width, height, layout) = read_image(&input);
+                encode_from_u8(&bytes, width, height, layout)
+            };
+            let stats = analyze(&encoded).unwrap_or_else(|err| panic!("{}: {}", input, err));
+            print_stats(&stats);
+        }
+        Opt::Heatmap {
+            input,
+            output,
+            bits_per_pixel,
+        } => {
+            let encoded = std::fs::read(&input).expect("invalid input file");
+            let kind = if bits_per_pixel {
+                Heatmap::BitsPerPixel
+            } else {
+                Heatmap::Ops
+            };
+            let image = heatmap(&encoded, kind).unwrap_or_else(|err| panic!("{}: {}", input, err));
+            let bytes = image
+                .pixels
+                .iter()
+                .flat_map(|pixel| [pixel.r, pixel.g, pixel.b])
+                .collect::<Vec<_>>();
+            write_png(
+                &output,
+                &bytes,
+                image.header.width,
+                image.header.height,
+                PixelLayout::RGB,
+            );
+        }
+    }
+}
+
+fn convert(input: &str, output: &str) {
+    println!("converting {} to {}", input, output);
+
+    let (bytes, width, height, layout) = read_image(input);
 
     println!("{}x{}", width, height);
 
-    match std::str::from_utf8(&opt.output.bytes().rev().take(3).rev().collect::<Vec<u8>>()).unwrap()
-    {
+    match std::str::from_utf8(&output.bytes().rev().take(3).rev().collect::<Vec<u8>>()).unwrap() {
         "png" => {
             println!("encoding png");
-            let file = File::create(&opt.output).unwrap();
-            let w = &mut BufWriter::new(file);
             let now = std::time::Instant::now();
-            let mut encoder = png::Encoder::new(w, width as u32, height as u32);
-            encoder.set_color(png::ColorType::Rgba);
-            encoder.set_depth(png::BitDepth::Eight);
-            let mut writer = encoder.write_header().unwrap();
-
-            // let data = [255, 0, 0, 255, 0, 0, 0, 255]; // An array containing a RGBA sequence. First pixel is red and second pixel is black.
-            writer.write_image_data(&bytes).unwrap(); // Save
+            write_png(output, &bytes, width, height, layout);
             println!("encoded in {:?}", now.elapsed());
         }
         "qoi" => {
             println!("encoding qoi");
             let now = std::time::SystemTime::now();
-            let encoded = encode_from_u8(&bytes, width, height); // save decoded to file
+            let encoded = encode_from_u8(&bytes, width, height, layout); // save decoded to file
             println!("encoded in {:?}", now.elapsed().unwrap());
-            let mut file = File::create(opt.output).unwrap();
+            print_stats(&analyze(&encoded).unwrap());
+            let mut file = File::create(output).unwrap();
             file.write_all(&encoded).unwrap();
         }
         _ => {
@@ -88,3 +198,96 @@ fn main() {
         }
     };
 }
+
+/// Reads a png or qoi file into bytes in the returned layout.
+fn read_image(input: &str) -> (Vec<u8>, u32, u32, PixelLayout) {
+    let file = File::open(input).expect("invalid input file");
+
+    let png_decoder = png::Decoder::new(file);
+    if let Ok(mut reader) = png_decoder.read_info() {
+        println!("decoding png");
+        // Allocate the output buffer.
+        let mut buf = vec![0; reader.output_buffer_size()];
+        // Read the next frame. An APNG might contain multiple frames.
+        let info = reader.next_frame(&mut buf).unwrap();
+        // Grab the bytes of the image.
+        println!("{:?}", info);
+        assert_eq!(
+            info.bit_depth,
+            png::BitDepth::Eight,
+            "png bit depth must be 8"
+        );
+        match info.color_type {
+            png::ColorType::Grayscale => todo!(),
+            png::ColorType::Rgb => {
+                buf.truncate(info.buffer_size());
+                (buf, info.width, info.height, PixelLayout::RGB)
+            }
+            png::ColorType::Indexed => todo!(),
+            png::ColorType::GrayscaleAlpha => todo!(),
+            png::ColorType::Rgba => {
+                buf.truncate(info.buffer_size());
+                (buf, info.width, info.height, PixelLayout::RGBA)
+            }
+        }
+    } else {
+        println!("decoding qoi");
+        let bytes = std::fs::read(input).unwrap();
+        let image = decode(&bytes);
+        (
+            image.to_u8(),
+            image.header.width,
+            image.header.height,
+            PixelLayout::RGBA,
+        )
+    }
+}
+
+/// Writes RGB or RGBA `bytes`, depending on `layout`, to a png file.
+fn write_png(output: &str, bytes: &[u8], width: u32, height: u32, layout: PixelLayout) {
+    let file = File::create(output).unwrap();
+    let w = &mut BufWriter::new(file);
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(match layout {
+        PixelLayout::RGB => png::ColorType::Rgb,
+        _ => png::ColorType::Rgba,
+    });
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+
+    // let data = [255, 0, 0, 255, 0, 0, 0, 255]; // An array containing a RGBA sequence. First pixel is red and second pixel is black.
+    writer.write_image_data(bytes).unwrap(); // Save
+}
+
+fn print_stats(stats: &EncodeStats) {
+    let header = &stats.header;
+    println!(
+        "{}x{}, {:?}, {:?}",
+        header.width, header.height, header.channels, header.color_space
+    );
+    println!("{:<6}{:>12}{:>12}", "op", "count", "bytes");
+    for (name, op) in [
+        ("RGB", stats.rgb),
+        ("RGBA", stats.rgba),
+        ("INDEX", stats.index),
+        ("DIFF", stats.diff),
+        ("LUMA", stats.luma),
+        ("RUN", stats.run),
+    ] {
+        println!("{:<6}{:>12}{:>12}", name, op.count, op.bytes);
+    }
+    println!("hash hit rate: {:.1}%", stats.hash_hit_rate() * 100.0);
+    println!(
+        "{} bytes, {:.3} bytes per pixel, {:.2}x smaller than raw RGB, {:.2}x smaller than raw RGBA",
+        stats.encoded_len,
+        stats.bytes_per_pixel(),
+        stats.compression_ratio(Channels::RGB),
+        stats.compression_ratio(Channels::RGBA)
+    );
+    println!("run lengths:");
+    for (len, count) in stats.run_lengths.iter().enumerate() {
+        if *count > 0 {
+            println!("{:>6}{:>12}", len, count);
+        }
+    }
+}
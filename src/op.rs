@@ -0,0 +1,204 @@
+use alloc::vec::Vec;
+
+use crate::{
+    num_pixels, Chunk, OpDiff, OpIndex, OpLuma, OpRGB, OpRGBA, OpRun, QoiError, QoiHeader,
+    END_MARKER,
+};
+
+/// A single op of a QOI stream, with its fields as they are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// A pixel with the alpha of the previous one.
+    RGB {
+        r: u8,
+        g: u8,
+        b: u8,
+    },
+    RGBA {
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+    },
+    /// A pixel from the hash of previously seen pixels.
+    Index(u8),
+    /// Differences to the previous pixel, each in -2..=1.
+    Diff {
+        dr: i8,
+        dg: i8,
+        db: i8,
+    },
+    /// The green difference to the previous pixel in -32..=31,
+    /// and the red and blue differences relative to it, each in -8..=7.
+    Luma {
+        dg: i8,
+        dr_dg: i8,
+        db_dg: i8,
+    },
+    /// The previous pixel repeated 1..=62 times.
+    Run(u8),
+}
+
+impl Op {
+    /// Parses the op at the start of `bytes`, or returns `None` if it is cut off.
+    pub fn parse(bytes: &[u8]) -> Option<Op> {
+        Chunk::from_encoding(bytes).map(|chunk| Op::from(&chunk))
+    }
+
+    /// Number of bytes this op takes up in the stream.
+    pub fn size(&self) -> usize {
+        match self {
+            Op::RGB { .. } => 4,
+            Op::RGBA { .. } => 5,
+            Op::Index(_) | Op::Diff { .. } | Op::Run(_) => 1,
+            Op::Luma { .. } => 2,
+        }
+    }
+
+    /// Number of pixels this op produces.
+    pub fn pixels(&self) -> usize {
+        match self {
+            Op::Run(run) => *run as usize,
+            _ => 1,
+        }
+    }
+
+    /// Appends the bytes of this op to `encoded`,
+    /// or fails with [`QoiError::InvalidOp`] if a field is outside of the range documented on the variant.
+    pub fn append_to(&self, encoded: &mut Vec<u8>) -> Result<(), QoiError> {
+        if !self.is_valid() {
+            return Err(QoiError::InvalidOp(*self));
+        }
+        match *self {
+            Op::RGB { r, g, b } => encoded.extend_from_slice(&OpRGB::new(r, g, b).get_encoding()),
+            Op::RGBA { r, g, b, a } => {
+                encoded.extend_from_slice(&OpRGBA::new(r, g, b, a).get_encoding())
+            }
+            Op::Index(index) => encoded.push(OpIndex::new(index).get_encoding()),
+            Op::Diff { dr, dg, db } => encoded.push(OpDiff::new(dr, dg, db).get_encoding()),
+            Op::Luma { dg, dr_dg, db_dg } => {
+                encoded.extend_from_slice(&OpLuma::new(dr_dg + dg, dg, db_dg + dg).get_encoding())
+            }
+            // runs are stored with a bias of -1
+            Op::Run(run) => encoded.push(OpRun::new(run - 1).get_encoding()),
+        }
+        Ok(())
+    }
+
+    /// Whether all fields are within the ranges documented on the variants,
+    /// which is always the case for ops that were parsed.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Op::RGB { .. } | Op::RGBA { .. } => true,
+            Op::Index(index) => index < 64,
+            Op::Diff { dr, dg, db } => [dr, dg, db].iter().all(|d| (-2..=1).contains(d)),
+            Op::Luma { dg, dr_dg, db_dg } => {
+                (-32..=31).contains(&dg) && (-8..=7).contains(&dr_dg) && (-8..=7).contains(&db_dg)
+            }
+            Op::Run(run) => (1..=62).contains(&run),
+        }
+    }
+}
+
+impl From<&Chunk> for Op {
+    fn from(chunk: &Chunk) -> Self {
+        match chunk {
+            Chunk::RGB(rgb) => Op::RGB {
+                r: rgb.r,
+                g: rgb.g,
+                b: rgb.b,
+            },
+            Chunk::RGBA(rgba) => Op::RGBA {
+                r: rgba.r,
+                g: rgba.g,
+                b: rgba.b,
+                a: rgba.a,
+            },
+            Chunk::Index(index) => Op::Index(index.index),
+            Chunk::Diff(diff) => {
+                let (dr, dg, db) = diff.get_diffs();
+                Op::Diff { dr, dg, db }
+            }
+            Chunk::Luma(luma) => Op::Luma {
+                dg: luma.dg as i8 - 32,
+                dr_dg: (luma.rb >> 4) as i8 - 8,
+                db_dg: (luma.rb & 0b1111) as i8 - 8,
+            },
+            // runs are stored with a bias of -1
+            Chunk::Run(run) => Op::Run(run.run + 1),
+        }
+    }
+}
+
+/// Walks the ops of `encoded`, yielding each with the offset of its first byte.
+///
+/// The header is only used for the number of pixels, to know where the end marker starts.
+/// An invalid header, a cut off op or a missing end marker are yielded as the last item.
+pub fn tokenize(encoded: &[u8]) -> Tokens<'_> {
+    Tokens {
+        encoded,
+        offset: 0,
+        remaining: None,
+        done: false,
+    }
+}
+
+/// The iterator returned by [`tokenize`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    encoded: &'a [u8],
+    offset: usize,
+    /// pixels left until the end marker, `None` before the header is read
+    remaining: Option<usize>,
+    done: bool,
+}
+
+impl Tokens<'_> {
+    fn next_op(&mut self) -> Result<Option<(usize, Op)>, QoiError> {
+        let remaining = match self.remaining {
+            Some(remaining) => remaining,
+            None => {
+                let header = QoiHeader::from_u8(self.encoded)?;
+                self.offset = 14;
+                *self
+                    .remaining
+                    .insert(num_pixels(header.width, header.height)?)
+            }
+        };
+
+        if remaining == 0 {
+            self.done = true;
+            let end = self
+                .encoded
+                .get(self.offset..self.offset + END_MARKER.len());
+            if end != Some(&END_MARKER[..]) {
+                return Err(QoiError::MissingEndMarker);
+            }
+            return Ok(None);
+        }
+
+        let op = Op::parse(&self.encoded[self.offset..]).ok_or(QoiError::Truncated {
+            offset: self.encoded.len(),
+        })?;
+        let offset = self.offset;
+        self.offset += op.size();
+        // a run that goes past the end of the image is cut off
+        self.remaining = Some(remaining.saturating_sub(op.pixels()));
+        Ok(Some((offset, op)))
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<(usize, Op), QoiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_op();
+        if result.is_err() {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
@@ -0,0 +1,96 @@
+use crate::Pixel;
+
+/// Counts how many pixels at the start of `pixels` are equal to `pixel`.
+pub(crate) type RunLength = fn(&[Pixel], Pixel) -> usize;
+
+/// Picks the fastest [`RunLength`] the CPU supports.
+pub(crate) fn select() -> RunLength {
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        return x86::avx2;
+    }
+    #[cfg(target_arch = "x86_64")]
+    return x86::sse2;
+    #[cfg(not(target_arch = "x86_64"))]
+    scalar
+}
+
+pub(crate) fn scalar(pixels: &[Pixel], pixel: Pixel) -> usize {
+    pixels
+        .iter()
+        .position(|&other| other != pixel)
+        .unwrap_or(pixels.len())
+}
+
+/// Every implementation the CPU supports, so tests can check that they agree.
+#[cfg(test)]
+pub(crate) fn available() -> alloc::vec::Vec<RunLength> {
+    #[allow(unused_mut)]
+    let mut available = alloc::vec![scalar as RunLength];
+    #[cfg(target_arch = "x86_64")]
+    available.push(x86::sse2);
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    if std::is_x86_feature_detected!("avx2") {
+        available.push(x86::avx2);
+    }
+    available
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::scalar;
+    use crate::Pixel;
+
+    /// A pixel as it is laid out in memory.
+    fn as_u32(pixel: Pixel) -> u32 {
+        u32::from_ne_bytes([pixel.r, pixel.g, pixel.b, pixel.a])
+    }
+
+    pub(super) fn sse2(pixels: &[Pixel], pixel: Pixel) -> usize {
+        // SAFETY: SSE2 is part of the x86_64 baseline
+        unsafe { sse2_impl(pixels, pixel) }
+    }
+
+    /// Must only be selected if the CPU supports AVX2.
+    #[cfg(feature = "std")]
+    pub(super) fn avx2(pixels: &[Pixel], pixel: Pixel) -> usize {
+        // SAFETY: only used after checking for AVX2
+        unsafe { avx2_impl(pixels, pixel) }
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn sse2_impl(pixels: &[Pixel], pixel: Pixel) -> usize {
+        let needle = _mm_set1_epi32(as_u32(pixel) as i32);
+        let mut i = 0;
+        while i + 4 <= pixels.len() {
+            // Pixel is repr(C) with four u8 fields, so four of them are 16 bytes
+            let chunk = _mm_loadu_si128(pixels.as_ptr().add(i) as *const __m128i);
+            let equal = _mm_cmpeq_epi32(chunk, needle);
+            let mask = _mm_movemask_ps(_mm_castsi128_ps(equal)) as u32;
+            if mask != 0b1111 {
+                return i + (!mask).trailing_zeros() as usize;
+            }
+            i += 4;
+        }
+        i + scalar(&pixels[i..], pixel)
+    }
+
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn avx2_impl(pixels: &[Pixel], pixel: Pixel) -> usize {
+        let needle = _mm256_set1_epi32(as_u32(pixel) as i32);
+        let mut i = 0;
+        while i + 8 <= pixels.len() {
+            let chunk = _mm256_loadu_si256(pixels.as_ptr().add(i) as *const __m256i);
+            let equal = _mm256_cmpeq_epi32(chunk, needle);
+            let mask = _mm256_movemask_ps(_mm256_castsi256_ps(equal)) as u32;
+            if mask != 0xFF {
+                return i + (!mask).trailing_zeros() as usize;
+            }
+            i += 8;
+        }
+        i + sse2_impl(&pixels[i..], pixel)
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{decoder::DecoderState, Chunk, DecodeLimits, Pixel, QoiError, QoiHeader, END_MARKER};
+
+/// Receives decoded pixels one at a time, in row-major order.
+///
+/// Implemented for closures taking `(x, y, pixel)`.
+pub trait PixelSink {
+    fn put(&mut self, x: u32, y: u32, pixel: Pixel);
+}
+
+impl<F: FnMut(u32, u32, Pixel)> PixelSink for F {
+    fn put(&mut self, x: u32, y: u32, pixel: Pixel) {
+        self(x, y, pixel)
+    }
+}
+
+/// A decoder that never allocates.
+///
+/// Input is pushed in pieces of any size with [`SinkDecoder::push`], and every pixel is handed
+/// to a [`PixelSink`] as soon as it is decoded. Apart from the hash, only a 14 byte window
+/// for a header, op or end marker split across two pieces is kept.
+#[derive(Debug, Clone)]
+pub struct SinkDecoder {
+    limits: DecodeLimits,
+    header: Option<QoiHeader>,
+    state: DecoderState,
+    window: [u8; 14],
+    window_len: usize,
+    x: u32,
+    y: u32,
+    /// whether the end marker has been read
+    finished: bool,
+    /// bytes consumed before `window`
+    consumed: usize,
+}
+
+impl Default for SinkDecoder {
+    fn default() -> Self {
+        SinkDecoder::new()
+    }
+}
+
+impl SinkDecoder {
+    /// Creates a decoder with the [default](DecodeLimits::default) limits.
+    pub fn new() -> Self {
+        SinkDecoder::with_limits(DecodeLimits::default())
+    }
+
+    /// Creates a decoder that fails as soon as the header shows that the image exceeds `limits`.
+    /// Nothing is allocated for the image, so `max_alloc_bytes` does not apply.
+    pub fn with_limits(limits: DecodeLimits) -> Self {
+        SinkDecoder {
+            limits,
+            header: None,
+            state: DecoderState::new(),
+            window: [0; 14],
+            window_len: 0,
+            x: 0,
+            y: 0,
+            finished: false,
+            consumed: 0,
+        }
+    }
+
+    /// The header, once enough input has been pushed to read it.
+    pub fn header(&self) -> Option<&QoiHeader> {
+        self.header.as_ref()
+    }
+
+    /// Decodes as much of `input` as possible, handing pixels to `sink`.
+    /// Input after the end marker is ignored.
+    ///
+    /// The decoder must not be used anymore after it returned an error.
+    pub fn push(&mut self, mut input: &[u8], sink: &mut impl PixelSink) -> Result<(), QoiError> {
+        while !input.is_empty() && !self.finished {
+            let (width, height) = match &self.header {
+                Some(header) => (header.width, header.height),
+                None => {
+                    if !self.fill(&mut input, 14) {
+                        break;
+                    }
+                    let header = QoiHeader::from_u8(&self.window)?;
+                    self.limits.check(&header, 0)?;
+                    self.header = Some(header);
+                    self.consume_window();
+                    continue;
+                }
+            };
+
+            if self.y == height || width == 0 {
+                if !self.fill(&mut input, END_MARKER.len()) {
+                    break;
+                }
+                if self.window[..END_MARKER.len()] != END_MARKER {
+                    return Err(QoiError::MissingEndMarker);
+                }
+                self.consume_window();
+                self.finished = true;
+                break;
+            }
+
+            // parse straight from the input if the whole op is there
+            let op = if self.window_len == 0 {
+                match Chunk::from_encoding(input) {
+                    Some(op) => {
+                        input = &input[op.size()..];
+                        self.consumed += op.size();
+                        op
+                    }
+                    None => {
+                        let size = Chunk::size_from_tag(input[0]);
+                        self.fill(&mut input, size);
+                        break;
+                    }
+                }
+            } else {
+                if !self.fill(&mut input, Chunk::size_from_tag(self.window[0])) {
+                    break;
+                }
+                let op = Chunk::from_encoding(&self.window[..self.window_len])
+                    .expect("the window holds a whole op");
+                self.consume_window();
+                op
+            };
+
+            let count = self.state.decode_chunk(&op);
+            for _ in 0..count {
+                sink.put(self.x, self.y, self.state.previous);
+                self.x += 1;
+                if self.x == width {
+                    self.x = 0;
+                    self.y += 1;
+                    // a run that goes past the end of the image is cut off
+                    if self.y == height {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the whole image and the end marker have been pushed and returns the header.
+    pub fn finish(self) -> Result<QoiHeader, QoiError> {
+        let offset = self.consumed + self.window_len;
+        match self.header {
+            None => Err(QoiError::Truncated { offset }),
+            Some(header) if self.finished => Ok(header),
+            Some(header) if self.y == header.height || header.width == 0 => {
+                Err(QoiError::MissingEndMarker)
+            }
+            Some(_) => Err(QoiError::Truncated { offset }),
+        }
+    }
+
+    /// Moves bytes from `input` to the window until it holds `len` bytes.
+    /// Returns whether there were enough.
+    fn fill(&mut self, input: &mut &[u8], len: usize) -> bool {
+        let missing = len.saturating_sub(self.window_len).min(input.len());
+        self.window[self.window_len..self.window_len + missing].copy_from_slice(&input[..missing]);
+        self.window_len += missing;
+        *input = &input[missing..];
+        self.window_len >= len
+    }
+
+    fn consume_window(&mut self) {
+        self.consumed += self.window_len;
+        self.window_len = 0;
+    }
+}
+
+/// Decodes `encoded` without allocating, handing every pixel to `sink`, and returns the header.
+pub fn decode_to_sink(encoded: &[u8], sink: &mut impl PixelSink) -> Result<QoiHeader, QoiError> {
+    let mut decoder = SinkDecoder::new();
+    decoder.push(encoded, sink)?;
+    decoder.finish()
+}
@@ -0,0 +1,104 @@
+use alloc::vec::Vec;
+
+use crate::{tokenize, try_encode, Channels, Op, Pixel, QoiError, QoiHeader};
+
+/// How often an op was used and how many bytes it took up in total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl OpStats {
+    fn add(&mut self, op: &Op) {
+        self.count += 1;
+        self.bytes += op.size();
+    }
+}
+
+/// What an encoded image is made of, see [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeStats {
+    pub header: QoiHeader,
+    /// The whole stream, including the header and end marker.
+    pub encoded_len: usize,
+    pub rgb: OpStats,
+    pub rgba: OpStats,
+    pub index: OpStats,
+    pub diff: OpStats,
+    pub luma: OpStats,
+    pub run: OpStats,
+    /// `run_lengths[n]` is the number of runs of `n` pixels, so the first entry is always 0.
+    pub run_lengths: [usize; 63],
+}
+
+impl EncodeStats {
+    /// width * height
+    pub fn num_pixels(&self) -> usize {
+        self.header.width as usize * self.header.height as usize
+    }
+
+    /// The share of pixels found in the hash, out of all that were not part of a run.
+    pub fn hash_hit_rate(&self) -> f64 {
+        let looked_up =
+            self.rgb.count + self.rgba.count + self.index.count + self.diff.count + self.luma.count;
+        if looked_up == 0 {
+            return 0.0;
+        }
+        self.index.count as f64 / looked_up as f64
+    }
+
+    /// Bytes of the whole stream per pixel.
+    pub fn bytes_per_pixel(&self) -> f64 {
+        if self.num_pixels() == 0 {
+            return 0.0;
+        }
+        self.encoded_len as f64 / self.num_pixels() as f64
+    }
+
+    /// How many times larger the pixels are as raw bytes with the given `channels`.
+    pub fn compression_ratio(&self, channels: Channels) -> f64 {
+        (self.num_pixels() * channels as usize) as f64 / self.encoded_len as f64
+    }
+}
+
+/// Counts the ops of a valid QOI stream.
+pub fn analyze(encoded: &[u8]) -> Result<EncodeStats, QoiError> {
+    let mut stats = EncodeStats {
+        header: QoiHeader::from_u8(encoded)?,
+        encoded_len: encoded.len(),
+        rgb: OpStats::default(),
+        rgba: OpStats::default(),
+        index: OpStats::default(),
+        diff: OpStats::default(),
+        luma: OpStats::default(),
+        run: OpStats::default(),
+        run_lengths: [0; 63],
+    };
+    for token in tokenize(encoded) {
+        let (_, op) = token?;
+        match op {
+            Op::RGB { .. } => stats.rgb.add(&op),
+            Op::RGBA { .. } => stats.rgba.add(&op),
+            Op::Index(_) => stats.index.add(&op),
+            Op::Diff { .. } => stats.diff.add(&op),
+            Op::Luma { .. } => stats.luma.add(&op),
+            Op::Run(run) => {
+                stats.run.add(&op);
+                stats.run_lengths[run as usize] += 1;
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Like [`try_encode`], but also returns the [`analyze`]d stats of the result.
+pub fn encode_with_stats(
+    pixels: &[Pixel],
+    width: u32,
+    height: u32,
+) -> Result<(Vec<u8>, EncodeStats), QoiError> {
+    let encoded = try_encode(pixels, width, height)?;
+    let stats = analyze(&encoded)?;
+    Ok((encoded, stats))
+}
@@ -0,0 +1,276 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    decoder::DecoderState, encoder::EncoderState, header, Channels, Chunk, ColorSpace,
+    DecodeLimits, Pixel, PixelLayout, QoiError, QoiHeader, END_MARKER,
+};
+
+/// Encodes an image row by row into a [`Write`]r.
+///
+/// Only the ops of the current row are buffered, so memory use does not depend on the image size.
+pub struct Encoder<W: Write> {
+    writer: W,
+    width: u32,
+    height: u32,
+    rows_written: u32,
+    state: EncoderState,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Creates a new encoder and writes the header to `writer`.
+    pub fn new(
+        mut writer: W,
+        width: u32,
+        height: u32,
+        channels: Channels,
+        color_space: ColorSpace,
+    ) -> io::Result<Self> {
+        writer.write_all(&header(width, height, channels, color_space))?;
+        Ok(Encoder {
+            writer,
+            width,
+            height,
+            rows_written: 0,
+            state: EncoderState::new(),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Encodes the next row, which has to be exactly `width` pixels long.
+    pub fn write_row(&mut self, row: &[Pixel]) -> io::Result<()> {
+        self.check_row(row.len())?;
+        for pixel in row {
+            self.state.encode_pixel(*pixel, &mut self.buffer);
+        }
+        self.write_buffer()
+    }
+
+    /// Encodes the next row from bytes in the given `layout`,
+    /// which have to be exactly `width * layout.bytes_per_pixel()` bytes long.
+    pub fn write_row_u8(&mut self, row: &[u8], layout: PixelLayout) -> io::Result<()> {
+        let bytes_per_pixel = layout.bytes_per_pixel();
+        if row.len() != self.width as usize * bytes_per_pixel {
+            return Err(QoiError::ByteCountMismatch {
+                expected: self.width as usize * bytes_per_pixel,
+                actual: row.len(),
+            }
+            .into());
+        }
+        self.check_row(self.width as usize)?;
+        for chunk in row.chunks_exact(bytes_per_pixel) {
+            self.state
+                .encode_pixel(layout.read(chunk), &mut self.buffer);
+        }
+        self.write_buffer()
+    }
+
+    /// Writes the pending run and the end marker and returns the underlying writer.
+    /// Fails if fewer than `height` rows were written.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.rows_written != self.height {
+            return Err(QoiError::PixelCountMismatch {
+                expected: (self.width as usize).saturating_mul(self.height as usize),
+                actual: (self.width as usize).saturating_mul(self.rows_written as usize),
+            }
+            .into());
+        }
+        self.state.flush_run(&mut self.buffer);
+        self.buffer.extend_from_slice(&END_MARKER);
+        self.write_buffer()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn check_row(&mut self, len: usize) -> Result<(), QoiError> {
+        if len != self.width as usize {
+            return Err(QoiError::PixelCountMismatch {
+                expected: self.width as usize,
+                actual: len,
+            });
+        }
+        if self.rows_written == self.height {
+            return Err(QoiError::PixelCountMismatch {
+                expected: (self.width as usize).saturating_mul(self.height as usize),
+                actual: (self.width as usize).saturating_mul(self.height as usize + 1),
+            });
+        }
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    fn write_buffer(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// Decodes an image row by row from a [`Read`]er.
+///
+/// Only a small window of the input is buffered, so memory use does not depend on the image size.
+pub struct Decoder<R: Read> {
+    reader: R,
+    header: QoiHeader,
+    state: DecoderState,
+    /// pixels left in the current run, including the one at `state.previous`
+    run: usize,
+    rows_read: u32,
+    buffer: Vec<u8>,
+    start: usize,
+    end: usize,
+    /// bytes consumed before `buffer[start]`
+    consumed: usize,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Creates a new decoder and reads the header from `reader`,
+    /// with the [default](DecodeLimits::default) limits.
+    pub fn new(reader: R) -> io::Result<Self> {
+        Decoder::with_limits(reader, DecodeLimits::default())
+    }
+
+    /// Like [`Decoder::new`], but fails right after reading the header if the image exceeds `limits`.
+    /// The rows are allocated by the caller, so `max_alloc_bytes` does not apply.
+    pub fn with_limits(mut reader: R, limits: DecodeLimits) -> io::Result<Self> {
+        let mut header = [0; 14];
+        let mut len = 0;
+        while len < header.len() {
+            match reader.read(&mut header[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let header = QoiHeader::from_u8(&header[..len])?;
+        limits.check(&header, 0)?;
+
+        let mut decoder = Decoder {
+            reader,
+            header,
+            state: DecoderState::new(),
+            run: 0,
+            rows_read: 0,
+            buffer: vec![0; 4096],
+            start: 0,
+            end: 0,
+            consumed: 14,
+        };
+        if decoder.header.height == 0 {
+            // there are no rows to read, so the end marker has to come right away
+            decoder.read_end_marker()?;
+        }
+        Ok(decoder)
+    }
+
+    pub fn header(&self) -> &QoiHeader {
+        &self.header
+    }
+
+    /// Decodes the next row into `row`, which has to be exactly `width` pixels long.
+    /// Returns `Ok(false)` without touching `row` once all rows have been read.
+    pub fn read_row(&mut self, row: &mut [Pixel]) -> io::Result<bool> {
+        if !self.start_row(row.len())? {
+            return Ok(false);
+        }
+        for pixel in row.iter_mut() {
+            *pixel = self.next_pixel()?;
+        }
+        self.finish_row()?;
+        Ok(true)
+    }
+
+    /// Decodes the next row into `row` as bytes in the given `layout`,
+    /// which has to be exactly `width * layout.bytes_per_pixel()` bytes long.
+    /// Returns `Ok(false)` without touching `row` once all rows have been read.
+    pub fn read_row_u8(&mut self, row: &mut [u8], layout: PixelLayout) -> io::Result<bool> {
+        let bytes_per_pixel = layout.bytes_per_pixel();
+        if row.len() != self.header.width as usize * bytes_per_pixel {
+            return Err(QoiError::ByteCountMismatch {
+                expected: self.header.width as usize * bytes_per_pixel,
+                actual: row.len(),
+            }
+            .into());
+        }
+        if !self.start_row(self.header.width as usize)? {
+            return Ok(false);
+        }
+        for chunk in row.chunks_exact_mut(bytes_per_pixel) {
+            let pixel = self.next_pixel()?;
+            layout.write(pixel, chunk);
+        }
+        self.finish_row()?;
+        Ok(true)
+    }
+
+    fn start_row(&mut self, len: usize) -> Result<bool, QoiError> {
+        if len != self.header.width as usize {
+            return Err(QoiError::PixelCountMismatch {
+                expected: self.header.width as usize,
+                actual: len,
+            });
+        }
+        Ok(self.rows_read < self.header.height)
+    }
+
+    fn finish_row(&mut self) -> io::Result<()> {
+        self.rows_read += 1;
+        if self.rows_read == self.header.height {
+            // a run that goes past the end of the image is cut off
+            self.run = 0;
+            self.read_end_marker()?;
+        }
+        Ok(())
+    }
+
+    fn read_end_marker(&mut self) -> io::Result<()> {
+        self.fill(END_MARKER.len())?;
+        if self.buffer[self.start..self.end].get(..END_MARKER.len()) != Some(&END_MARKER[..]) {
+            return Err(QoiError::MissingEndMarker.into());
+        }
+        self.consume(END_MARKER.len());
+        Ok(())
+    }
+
+    fn next_pixel(&mut self) -> io::Result<Pixel> {
+        if self.run == 0 {
+            // the largest op is 5 bytes long
+            self.fill(5)?;
+            let op = Chunk::from_encoding(&self.buffer[self.start..self.end]).ok_or(
+                QoiError::Truncated {
+                    offset: self.consumed + self.end - self.start,
+                },
+            )?;
+            self.consume(op.size());
+            self.run = self.state.decode_chunk(&op);
+        }
+        self.run -= 1;
+        Ok(self.state.previous)
+    }
+
+    /// Makes sure that at least `needed` bytes are buffered, unless the input ends first.
+    fn fill(&mut self, needed: usize) -> io::Result<()> {
+        if self.end - self.start >= needed {
+            return Ok(());
+        }
+        // move the unread bytes to the front to make room
+        self.buffer.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        while self.end < needed {
+            match self.reader.read(&mut self.buffer[self.end..]) {
+                Ok(0) => break,
+                Ok(read) => self.end += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        self.start += bytes;
+        self.consumed += bytes;
+    }
+}
@@ -1,11 +1,15 @@
+use alloc::vec::Vec;
+
+use crate::QoiError;
+
 #[repr(u8)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Channels {
     RGB = 3,
     RGBA = 4,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ColorSpace {
     SRGB = 0,
     Linear = 1,
@@ -21,16 +25,20 @@ pub struct QoiHeader {
 }
 
 impl QoiHeader {
-    pub fn from_u8(header: &[u8]) -> Result<Self, String> {
+    pub fn from_u8(header: &[u8]) -> Result<Self, QoiError> {
         if header.len() < 14 {
-            return Err(format!("Header is too short: {}", header.len()));
+            return Err(QoiError::Truncated {
+                offset: header.len(),
+            });
         }
         if header[0] != "qoif".as_bytes()[0]
             || header[1] != "qoif".as_bytes()[1]
             || header[2] != "qoif".as_bytes()[2]
             || header[3] != "qoif".as_bytes()[3]
         {
-            return Err(format!("Invalid magic: {:?}", &header[0..4]));
+            return Err(QoiError::InvalidMagic([
+                header[0], header[1], header[2], header[3],
+            ]));
         }
 
         let width = ((header[4] as u32) << 24)
@@ -44,12 +52,12 @@ impl QoiHeader {
         let channels = match header[12] {
             3 => Channels::RGB,
             4 => Channels::RGBA,
-            _ => return Err(format!("Invalid number of channels: {}", header[12])),
+            _ => return Err(QoiError::InvalidChannels(header[12])),
         };
         let colorspace = match header[13] {
             0 => ColorSpace::SRGB,
             1 => ColorSpace::Linear,
-            _ => return Err(format!("Invalid color space: {}", header[13])),
+            _ => return Err(QoiError::InvalidColorSpace(header[13])),
         };
         Ok(QoiHeader {
             magic: [header[0], header[1], header[2], header[3]],
@@ -61,7 +69,26 @@ impl QoiHeader {
     }
 }
 
+/// A decoded image together with the header it was stored with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QoiImage {
+    pub header: QoiHeader,
+    pub pixels: Vec<Pixel>,
+}
+
+impl QoiImage {
+    /// Returns the pixels as tightly packed RGBA bytes.
+    pub fn to_u8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a])
+            .collect()
+    }
+}
+
+/// `repr(C)`, so a slice of pixels can be read as RGBA bytes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
 pub struct Pixel {
     pub r: u8,
     pub g: u8,
@@ -81,6 +108,7 @@ impl Default for Pixel {
 }
 
 impl Pixel {
+    #[cfg(any(test, feature = "random"))]
     pub fn random() -> Self {
         Pixel {
             r: rand::random(),
@@ -93,13 +121,19 @@ impl Pixel {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct QOIHash {
-    data: Box<[Pixel]>,
+    data: [Pixel; 64],
 }
 
 impl QOIHash {
     pub(crate) fn new() -> Self {
         QOIHash {
-            data: vec![Pixel::default(); 64].into_boxed_slice(),
+            // the spec requires a zero-initialized array, which is not Pixel::default()
+            data: [Pixel {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 0,
+            }; 64],
         }
     }
 
@@ -122,7 +156,7 @@ impl QOIHash {
         if self.data[index] == *pixel {
             return Some(index as u8);
         }
-        return None;
+        None
     }
 
     pub(crate) fn insert(&mut self, pixel: &Pixel) {
@@ -201,9 +235,9 @@ pub(crate) struct OpDiff {
 
 impl OpDiff {
     pub(crate) fn new(r: i8, g: i8, b: i8) -> Self {
-        debug_assert!(r >= -2 && r <= 1);
-        debug_assert!(g >= -2 && g <= 1);
-        debug_assert!(b >= -2 && b <= 1);
+        debug_assert!((-2..=1).contains(&r));
+        debug_assert!((-2..=1).contains(&g));
+        debug_assert!((-2..=1).contains(&b));
         OpDiff {
             diff: (((r + 2) as u8) << 4) | (((g + 2) as u8) << 2) | ((b + 2) as u8),
         }
@@ -220,7 +254,7 @@ impl OpDiff {
     pub fn get_diffs(&self) -> (i8, i8, i8) {
         let r = ((self.diff >> 4) & 0b11) as i8 - 2;
         let g = ((self.diff >> 2) & 0b11) as i8 - 2;
-        let b = ((self.diff >> 0) & 0b11) as i8 - 2;
+        let b = (self.diff & 0b11) as i8 - 2;
         (r, g, b)
     }
 }
@@ -233,12 +267,12 @@ pub(crate) struct OpLuma {
 
 impl OpLuma {
     pub(crate) fn new(dr: i8, dg: i8, db: i8) -> Self {
-        debug_assert!(dg >= -32 && dg <= 31);
-        debug_assert!(dr - dg >= -8 && dr - dg <= 7);
-        debug_assert!(db - dg >= -8 && db - dg <= 7);
+        debug_assert!((-32..=31).contains(&dg));
+        debug_assert!((-8..=7).contains(&dr.wrapping_sub(dg)));
+        debug_assert!((-8..=7).contains(&db.wrapping_sub(dg)));
         OpLuma {
             dg: (dg + 32) as u8,
-            rb: ((dr - dg + 8) as u8) << 4 | ((db - dg + 8) as u8),
+            rb: ((dr.wrapping_sub(dg) + 8) as u8) << 4 | ((db.wrapping_sub(dg) + 8) as u8),
         }
     }
 
@@ -253,8 +287,8 @@ impl OpLuma {
     pub fn get_diffs(&self) -> (i8, i8, i8) {
         let dg = self.dg as i8 - 32;
         let dr = ((self.rb >> 4) & 0b1111) as i8 - 8;
-        let db = ((self.rb >> 0) & 0b1111) as i8 - 8;
-        (dr + dg, dg, db + dg)
+        let db = (self.rb & 0b1111) as i8 - 8;
+        (dr.wrapping_add(dg), dg, db.wrapping_add(dg))
     }
 }
 
@@ -289,37 +323,52 @@ pub(crate) enum Chunk {
 }
 
 impl Chunk {
-    pub(crate) fn from_encoding(possible_chunk: &[u8]) -> Self {
-        // dbg!(&possible_chunk);
-        let op = possible_chunk[0];
-        match (op & 0b11000000) >> 6 {
+    /// Parses the op at the start of `possible_chunk`.
+    /// Returns `None` if the slice is too short to contain the whole op.
+    pub(crate) fn from_encoding(possible_chunk: &[u8]) -> Option<Self> {
+        let op = *possible_chunk.first()?;
+        let chunk = match (op & 0b11000000) >> 6 {
             0b00 => Chunk::Index(OpIndex::new(op & 0b00111111)),
             0b01 => Chunk::Diff(OpDiff {
                 diff: op & 0b00111111,
             }),
             0b10 => Chunk::Luma(OpLuma {
                 dg: op & 0b00111111,
-                rb: possible_chunk[1],
+                rb: *possible_chunk.get(1)?,
             }),
             0b11 => {
                 if op == 0b11111110 {
-                    Chunk::RGB(OpRGB::new(
-                        possible_chunk[1],
-                        possible_chunk[2],
-                        possible_chunk[3],
-                    ))
+                    let rgb = possible_chunk.get(1..4)?;
+                    Chunk::RGB(OpRGB::new(rgb[0], rgb[1], rgb[2]))
                 } else if op == 0b11111111 {
-                    Chunk::RGBA(OpRGBA::new(
-                        possible_chunk[1],
-                        possible_chunk[2],
-                        possible_chunk[3],
-                        possible_chunk[4],
-                    ))
+                    let rgba = possible_chunk.get(1..5)?;
+                    Chunk::RGBA(OpRGBA::new(rgba[0], rgba[1], rgba[2], rgba[3]))
                 } else {
                     Chunk::Run(OpRun::new(op & 0b00111111))
                 }
             }
             _ => unreachable!(),
+        };
+        Some(chunk)
+    }
+
+    /// Number of bytes this op takes up in the stream.
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Chunk::RGB(_) => 4,
+            Chunk::RGBA(_) => 5,
+            Chunk::Index(_) | Chunk::Diff(_) | Chunk::Run(_) => 1,
+            Chunk::Luma(_) => 2,
+        }
+    }
+
+    /// Number of bytes the op starting with `op` takes up in the stream.
+    pub(crate) fn size_from_tag(op: u8) -> usize {
+        match op {
+            0b11111110 => 4,
+            0b11111111 => 5,
+            _ if op >> 6 == 0b10 => 2,
+            _ => 1,
         }
     }
 }
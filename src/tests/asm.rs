@@ -0,0 +1,121 @@
+use rand::Rng;
+
+use crate::{
+    assemble, decode_to_pix, disassemble, encode_from_pix, header, AsmError, Channels, ColorSpace,
+    Pixel, END_MARKER,
+};
+
+#[test]
+fn round_trip() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        let pixels = (0..width * height)
+            .map(|_| [Pixel::random(), Pixel::default()][rng.gen_range(0..2)])
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width, height);
+
+        assert_eq!(assemble(&disassemble(&encoded)).unwrap(), encoded);
+    }
+}
+
+#[test]
+fn every_op() {
+    let text = "
+        # a run at the start repeats the initial pixel
+        header 7 1 rgba srgb
+        RUN 1
+        RGB 10 20 30
+        RGBA 10 20 30 40
+        INDEX 63  # never written to
+        DIFF -2 -1 1
+        LUMA -32 7 -8
+        RUN 62
+        END
+    ";
+    let encoded = assemble(text).unwrap();
+
+    let mut expected = header(7, 1, Channels::RGBA, ColorSpace::SRGB).to_vec();
+    let ops: [&[u8]; 7] = [
+        &[0b11_000000],
+        &[0b11111110, 10, 20, 30],
+        &[0b11111111, 10, 20, 30, 40],
+        &[0b00_111111],
+        &[0b01_00_01_11],
+        &[0b10_000000, 0b1111_0000],
+        &[0b11_111101],
+    ];
+    expected.extend(ops.concat());
+    expected.extend_from_slice(&END_MARKER);
+    assert_eq!(encoded, expected);
+
+    assert_eq!(
+        disassemble(&encoded),
+        "HEADER 7 1 RGBA SRGB\nRUN 1\nRGB 10 20 30\nRGBA 10 20 30 40\nINDEX 63\n\
+         DIFF -2 -1 1\nLUMA -32 7 -8\nRUN 62\nEND\n"
+    );
+    // the hash starts out zeroed
+    assert_eq!(
+        decode_to_pix(&encoded)[3],
+        Pixel {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0
+        }
+    );
+}
+
+#[test]
+fn broken_streams() {
+    // neither the pixel count nor the end marker are checked
+    let encoded = assemble("HEADER 2 2 RGB LINEAR\nRUN 1").unwrap();
+    let mut expected = header(2, 2, Channels::RGB, ColorSpace::Linear).to_vec();
+    expected.push(0b11_000000);
+    assert_eq!(encoded, expected);
+
+    assert_eq!(
+        disassemble(&encoded),
+        "HEADER 2 2 RGB LINEAR\nRUN 1\n# error: stream is truncated at byte 15\n"
+    );
+    assert_eq!(
+        disassemble(&encoded[..10]),
+        "# error: stream is truncated at byte 10\n"
+    );
+}
+
+#[test]
+fn errors() {
+    let error = |line, message: &str| {
+        Err(AsmError {
+            line,
+            message: message.to_string(),
+        })
+    };
+    assert_eq!(assemble("RUN 1"), error(1, "expected HEADER first"));
+    assert_eq!(assemble("# nothing\n"), error(2, "missing HEADER"));
+    assert_eq!(
+        assemble("HEADER 1 1 RGB"),
+        error(
+            1,
+            "HEADER takes a width, a height, channels and a color space"
+        )
+    );
+    assert_eq!(
+        assemble("HEADER 1 1 RGB SRGB\n\nRUN 63"),
+        error(3, "expected a number in 1..=62, got \"63\"")
+    );
+    assert_eq!(
+        assemble("HEADER 1 1 RGB SRGB\nDIFF 1 2 x"),
+        error(2, "expected a number in -2..=1, got \"2\"")
+    );
+    assert_eq!(
+        assemble("HEADER 1 1 RGB SRGB\nRGB 1 2"),
+        error(2, "RGB takes 3 arguments")
+    );
+    assert_eq!(
+        assemble("HEADER 1 1 RGB SRGB\nNOP"),
+        error(2, "unknown op \"NOP\"")
+    );
+}
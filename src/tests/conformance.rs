@@ -0,0 +1,123 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    decode_to_u8, encode_from_u8, encode_reference, Channels, ColorSpace, PixelLayout, QoiHeader,
+};
+
+const IMAGES: [&str; 12] = [
+    "alpha_ramp",
+    "flat",
+    "gradient",
+    "long_runs",
+    "noise",
+    "noise_alpha",
+    "opaque",
+    "palette",
+    "single",
+    "smooth",
+    "transparent",
+    "wrapping",
+];
+
+struct Image {
+    bytes: Vec<u8>,
+    width: u32,
+    height: u32,
+    channels: Channels,
+    /// encoded by the reference implementation
+    expected: Vec<u8>,
+}
+
+fn load(name: &str) -> Image {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let file = fs::File::open(dir.join(format!("{}.png", name))).unwrap();
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut bytes = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut bytes).unwrap();
+    bytes.truncate(info.buffer_size());
+    let channels = match info.color_type {
+        png::ColorType::Rgb => Channels::RGB,
+        png::ColorType::Rgba => Channels::RGBA,
+        other => panic!("unexpected color type {:?}", other),
+    };
+
+    Image {
+        bytes,
+        width: info.width,
+        height: info.height,
+        channels,
+        expected: fs::read(dir.join(format!("{}.qoi", name))).unwrap(),
+    }
+}
+
+fn layout(channels: &Channels) -> PixelLayout {
+    match channels {
+        Channels::RGB => PixelLayout::RGB,
+        Channels::RGBA => PixelLayout::RGBA,
+    }
+}
+
+#[test]
+fn reference_encoding() {
+    for name in IMAGES {
+        let image = load(name);
+
+        let encoded = encode_reference(
+            &image.bytes,
+            image.width,
+            image.height,
+            image.channels,
+            ColorSpace::SRGB,
+        )
+        .unwrap();
+
+        assert_eq!(encoded, image.expected, "{}", name);
+    }
+}
+
+#[test]
+fn default_encoding_has_the_same_ops() {
+    // only the channels in the header may differ, as they are picked based on the pixels
+    for name in IMAGES {
+        let image = load(name);
+
+        let encoded = encode_from_u8(
+            &image.bytes,
+            image.width,
+            image.height,
+            layout(&image.channels),
+        );
+
+        assert_eq!(encoded[..12], image.expected[..12], "{}", name);
+        assert_eq!(encoded[13..], image.expected[13..], "{}", name);
+    }
+}
+
+#[test]
+fn decode_reference() {
+    for name in IMAGES {
+        let image = load(name);
+
+        let header = QoiHeader::from_u8(&image.expected).unwrap();
+        assert_eq!(header.channels, image.channels, "{}", name);
+        assert_eq!(
+            decode_to_u8(&image.expected, layout(&image.channels)),
+            image.bytes,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn header_fields() {
+    let encoded =
+        encode_reference(&[1, 2, 3, 4], 1, 1, Channels::RGBA, ColorSpace::Linear).unwrap();
+    assert_eq!(encoded[12..14], [4, 1]);
+
+    // the channels are not picked based on alpha
+    let encoded =
+        encode_reference(&[100, 2, 3, 255], 1, 1, Channels::RGBA, ColorSpace::SRGB).unwrap();
+    assert_eq!(encoded[12..14], [4, 0]);
+    assert_eq!(encoded[14..18], [0b11111110, 100, 2, 3]);
+}
@@ -0,0 +1,81 @@
+use rand::Rng;
+
+use crate::{decode_into, decode_to_pix, encode_from_pix, Pixel, PixelLayout, QoiError};
+
+#[test]
+fn sub_rectangle() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        let pixels = (0..width * height)
+            .map(|_| Pixel {
+                a: rng.gen(),
+                ..Pixel::random()
+            })
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+
+        // decode into the middle of a canvas that is 3 pixels wider than the image
+        let stride = (width + 3) * 4;
+        let mut canvas = vec![42; stride * (height + 2)];
+        let offset = stride + 4;
+        let header =
+            decode_into(&encoded, &mut canvas[offset..], stride, PixelLayout::RGBA).unwrap();
+        assert_eq!((header.width, header.height), (width as u32, height as u32));
+
+        let decoded = decode_to_pix(&encoded);
+        for y in 0..height + 2 {
+            for x in 0..width + 3 {
+                let start = y * stride + x * 4;
+                let expected = if (1..=width).contains(&x) && (1..=height).contains(&y) {
+                    let pixel = decoded[(y - 1) * width + x - 1];
+                    [pixel.r, pixel.g, pixel.b, pixel.a]
+                } else {
+                    [42; 4]
+                };
+                assert_eq!(canvas[start..start + 4], expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn rgb_layout() {
+    let pixels = (0..6).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 3, 2);
+
+    // the last row does not need padding
+    let mut buffer = vec![0; 10 + 9];
+    decode_into(&encoded, &mut buffer, 10, PixelLayout::RGB).unwrap();
+
+    for (i, pixel) in pixels.iter().enumerate() {
+        let start = i / 3 * 10 + i % 3 * 3;
+        assert_eq!(buffer[start..start + 3], [pixel.r, pixel.g, pixel.b]);
+    }
+}
+
+#[test]
+fn invalid_stride() {
+    let encoded = encode_from_pix(&[Pixel::default(); 4], 2, 2);
+    let mut buffer = [0; 16];
+
+    assert_eq!(
+        decode_into(&encoded, &mut buffer, 7, PixelLayout::RGBA),
+        Err(QoiError::InvalidStride {
+            stride: 7,
+            row_len: 8
+        })
+    );
+}
+
+#[test]
+fn buffer_too_small() {
+    let encoded = encode_from_pix(&[Pixel::default(); 4], 2, 2);
+    let mut buffer = [0; 15];
+
+    assert_eq!(
+        decode_into(&encoded, &mut buffer, 8, PixelLayout::RGBA),
+        Err(QoiError::BufferTooSmall { len: 15 })
+    );
+}
@@ -0,0 +1,115 @@
+use std::io::Read;
+
+use rand::Rng;
+
+use super::qoi_error;
+use crate::{decode_to_pix, encode_from_pix, Decoder, Pixel, PixelLayout, QoiError};
+
+/// Hands out at most `max` bytes per call to `read`, so that ops get split up.
+struct Trickle<'a> {
+    data: &'a [u8],
+    max: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.max.min(buf.len()).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+fn stream_decode(encoded: &[u8], max: usize) -> Vec<Pixel> {
+    let mut decoder = Decoder::new(Trickle { data: encoded, max }).unwrap();
+    let width = decoder.header().width as usize;
+    let mut row = vec![Pixel::default(); width];
+    let mut pixels = Vec::new();
+    while decoder.read_row(&mut row).unwrap() {
+        pixels.extend_from_slice(&row);
+    }
+    pixels
+}
+
+#[test]
+fn matches_decode_to_pix() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..1_000 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        // few colors, so that there are runs across rows
+        let pixel_list = (0..3).map(|_| Pixel::random()).collect::<Vec<_>>();
+        let pixels = (0..width * height)
+            .map(|_| pixel_list[rng.gen_range(0..pixel_list.len())])
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+
+        let decoded = stream_decode(&encoded, rng.gen_range(1..8));
+
+        assert_eq!(decoded, decode_to_pix(&encoded));
+    }
+}
+
+#[test]
+fn long_run_across_rows() {
+    let pixels = vec![Pixel::default(); 100 * 3];
+    let encoded = encode_from_pix(&pixels, 100, 3);
+
+    assert_eq!(stream_decode(&encoded, 1), pixels);
+}
+
+#[test]
+fn header_before_pixels() {
+    let pixels = (0..6).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 3, 2);
+
+    // only the header is available, reading it must not need any pixel data
+    let decoder = Decoder::new(&encoded[..14]).unwrap();
+
+    assert_eq!((decoder.header().width, decoder.header().height), (3, 2));
+}
+
+#[test]
+fn u8_rows() {
+    let bytes = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 9, 10, 11, 12];
+    let encoded = crate::encode_from_u8(&bytes, 2, 2, PixelLayout::RGBA);
+
+    let mut decoder = Decoder::new(&encoded[..]).unwrap();
+    let mut decoded = [0; 16];
+    for row in decoded.chunks_mut(8) {
+        assert!(decoder.read_row_u8(row, PixelLayout::RGBA).unwrap());
+    }
+
+    assert!(!decoder.read_row_u8(&mut [0; 8], PixelLayout::RGBA).unwrap());
+    assert_eq!(decoded, bytes);
+}
+
+#[test]
+fn truncated() {
+    let pixels = (0..4).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 4, 1);
+    let truncated = &encoded[..encoded.len() - 10];
+
+    let mut decoder = Decoder::new(truncated).unwrap();
+    let err = decoder.read_row(&mut [Pixel::default(); 4]).unwrap_err();
+
+    assert_eq!(
+        qoi_error(err),
+        QoiError::Truncated {
+            offset: truncated.len()
+        }
+    );
+}
+
+#[test]
+fn missing_end_marker() {
+    let pixels = vec![Pixel::default(); 4];
+    let encoded = encode_from_pix(&pixels, 2, 2);
+
+    let mut decoder = Decoder::new(&encoded[..encoded.len() - 1]).unwrap();
+    let mut row = [Pixel::default(); 2];
+    decoder.read_row(&mut row).unwrap();
+    let err = decoder.read_row(&mut row).unwrap_err();
+
+    assert_eq!(qoi_error(err), QoiError::MissingEndMarker);
+}
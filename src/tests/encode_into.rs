@@ -0,0 +1,91 @@
+use rand::Rng;
+
+use crate::{
+    encode_from_pix, encode_from_u8, encode_from_u8_into, encode_into, max_encoded_len, Pixel,
+    PixelLayout, QoiError,
+};
+
+#[test]
+fn matches_encode_from_pix() {
+    let mut rng = rand::thread_rng();
+    let mut buffer = vec![0; max_encoded_len(9, 9).unwrap()];
+    for _ in 0..1_000 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        // random alpha, so that every pixel is as expensive as possible
+        let pixels = (0..width * height)
+            .map(|_| Pixel {
+                a: rng.gen(),
+                ..Pixel::random()
+            })
+            .collect::<Vec<_>>();
+
+        let len = encode_into(&pixels, width as u32, height as u32, &mut buffer).unwrap();
+
+        assert!(len <= max_encoded_len(width as u32, height as u32).unwrap());
+        assert_eq!(
+            &buffer[..len],
+            encode_from_pix(&pixels, width as u32, height as u32)
+        );
+    }
+}
+
+#[test]
+fn max_encoded_len_is_reached() {
+    // a different alpha for every pixel, so that every one of them is an OpRGBA
+    let pixels = (0..64)
+        .map(|a| Pixel {
+            a: a as u8,
+            ..Pixel::random()
+        })
+        .collect::<Vec<_>>();
+    let len = max_encoded_len(8, 8).unwrap();
+    let mut buffer = vec![0; len];
+
+    assert_eq!(encode_into(&pixels, 8, 8, &mut buffer), Ok(len));
+}
+
+#[test]
+fn max_encoded_len_overflow() {
+    assert_eq!(max_encoded_len(3, 2), Some(3 * 2 * 5 + 14 + 8));
+    if usize::BITS == 64 {
+        assert_eq!(max_encoded_len(u32::MAX, u32::MAX), None);
+    }
+}
+
+#[test]
+fn buffer_too_small() {
+    let pixels = vec![Pixel::default(); 4];
+    let mut buffer = [0; 20];
+
+    assert_eq!(
+        encode_into(&pixels, 2, 2, &mut buffer),
+        Err(QoiError::BufferTooSmall { len: 20 })
+    );
+}
+
+#[test]
+fn from_u8() {
+    let bytes = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 9, 10, 11, 12];
+    let mut buffer = [0; 64];
+
+    let len = encode_from_u8_into(&bytes, 2, 2, PixelLayout::RGBA, &mut buffer).unwrap();
+
+    assert_eq!(
+        &buffer[..len],
+        encode_from_u8(&bytes, 2, 2, PixelLayout::RGBA)
+    );
+}
+
+#[test]
+fn partial_pixel() {
+    let mut buffer = [0; 64];
+
+    assert_eq!(
+        encode_from_u8_into(&[0; 7], 2, 1, PixelLayout::RGBA, &mut buffer),
+        Err(QoiError::ByteCountMismatch {
+            expected: 8,
+            actual: 7
+        })
+    );
+}
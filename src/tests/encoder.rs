@@ -0,0 +1,95 @@
+use rand::Rng;
+
+use super::qoi_error;
+use crate::{
+    decode_to_pix, encode_from_pix, Channels, ColorSpace, Encoder, Pixel, PixelLayout, QoiError,
+};
+
+fn stream_encode(pixels: &[Pixel], width: usize, height: usize, channels: Channels) -> Vec<u8> {
+    let mut encoder = Encoder::new(
+        Vec::new(),
+        width as u32,
+        height as u32,
+        channels,
+        ColorSpace::SRGB,
+    )
+    .unwrap();
+    for row in pixels.chunks(width) {
+        encoder.write_row(row).unwrap();
+    }
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn matches_encode_from_pix() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..1_000 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        // few colors, so that there are runs across rows
+        let pixel_list = (0..3).map(|_| Pixel::random()).collect::<Vec<_>>();
+        let pixels = (0..width * height)
+            .map(|_| pixel_list[rng.gen_range(0..pixel_list.len())])
+            .collect::<Vec<_>>();
+
+        let encoded = stream_encode(&pixels, width, height, Channels::RGB);
+
+        assert_eq!(
+            encoded,
+            encode_from_pix(&pixels, width as u32, height as u32)
+        );
+    }
+}
+
+#[test]
+fn long_run_across_rows() {
+    let pixels = vec![Pixel::default(); 100 * 3];
+    let encoded = stream_encode(&pixels, 100, 3, Channels::RGB);
+
+    assert_eq!(encoded, encode_from_pix(&pixels, 100, 3));
+    assert_eq!(decode_to_pix(&encoded), pixels);
+}
+
+#[test]
+fn u8_rows() {
+    let bytes = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 9, 10, 11, 12];
+    let mut encoder = Encoder::new(Vec::new(), 2, 2, Channels::RGBA, ColorSpace::SRGB).unwrap();
+    for row in bytes.chunks(8) {
+        encoder.write_row_u8(row, PixelLayout::RGBA).unwrap();
+    }
+    let encoded = encoder.finish().unwrap();
+
+    assert_eq!(crate::decode_to_u8(&encoded, PixelLayout::RGBA), bytes);
+}
+
+#[test]
+fn wrong_row_length() {
+    let mut encoder = Encoder::new(Vec::new(), 2, 2, Channels::RGB, ColorSpace::SRGB).unwrap();
+    let err = encoder.write_row(&[Pixel::default(); 3]).unwrap_err();
+
+    assert_eq!(
+        qoi_error(err),
+        QoiError::PixelCountMismatch {
+            expected: 2,
+            actual: 3
+        }
+    );
+}
+
+#[test]
+fn wrong_row_count() {
+    let mut encoder = Encoder::new(Vec::new(), 2, 2, Channels::RGB, ColorSpace::SRGB).unwrap();
+    encoder.write_row(&[Pixel::default(); 2]).unwrap();
+    let err = encoder.finish().unwrap_err();
+    assert_eq!(
+        qoi_error(err),
+        QoiError::PixelCountMismatch {
+            expected: 4,
+            actual: 2
+        }
+    );
+
+    let mut encoder = Encoder::new(Vec::new(), 2, 1, Channels::RGB, ColorSpace::SRGB).unwrap();
+    encoder.write_row(&[Pixel::default(); 2]).unwrap();
+    assert!(encoder.write_row(&[Pixel::default(); 2]).is_err());
+}
@@ -0,0 +1,88 @@
+use crate::{assemble, heatmap, Channels, Heatmap, Op, Pixel, QoiError};
+
+fn encoded() -> Vec<u8> {
+    assemble(
+        "
+        HEADER 4 2 RGBA LINEAR
+        RGBA 1 2 3 4
+        RGB 5 6 7
+        INDEX 0
+        DIFF 0 0 0
+        LUMA 0 0 0
+        RUN 5  # cut off after 3 pixels
+        END
+        ",
+    )
+    .unwrap()
+}
+
+#[test]
+fn ops() {
+    let image = heatmap(&encoded(), Heatmap::Ops).unwrap();
+
+    assert_eq!((image.header.width, image.header.height), (4, 2));
+    assert_eq!(image.header.channels, Channels::RGB);
+    let run = Heatmap::op_color(&Op::Run(1));
+    assert_eq!(
+        image.pixels,
+        [
+            Heatmap::op_color(&Op::RGBA {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 0
+            }),
+            Heatmap::op_color(&Op::RGB { r: 0, g: 0, b: 0 }),
+            Heatmap::op_color(&Op::Index(0)),
+            Heatmap::op_color(&Op::Diff {
+                dr: 0,
+                dg: 0,
+                db: 0
+            }),
+            Heatmap::op_color(&Op::Luma {
+                dg: 0,
+                dr_dg: 0,
+                db_dg: 0
+            }),
+            run,
+            run,
+            run,
+        ]
+    );
+}
+
+#[test]
+fn bits_per_pixel() {
+    let image = heatmap(&encoded(), Heatmap::BitsPerPixel).unwrap();
+
+    let gray = |value| Pixel {
+        r: value,
+        g: value,
+        b: value,
+        a: 255,
+    };
+    // 40, 32, 8, 8, 16 and 8 / 5 bits
+    assert_eq!(
+        image.pixels,
+        [
+            gray(255),
+            gray(204),
+            gray(51),
+            gray(51),
+            gray(102),
+            gray(10),
+            gray(10),
+            gray(10)
+        ]
+    );
+}
+
+#[test]
+fn invalid_stream() {
+    let encoded = encoded();
+
+    assert_eq!(
+        heatmap(&encoded[..encoded.len() - 1], Heatmap::Ops),
+        Err(QoiError::MissingEndMarker)
+    );
+}
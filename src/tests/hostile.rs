@@ -0,0 +1,143 @@
+use rand::Rng;
+
+use crate::{
+    decode_into, encode_from_pix, try_decode, try_decode_with_limits, DecodeLimits, Decoder, Pixel,
+    PixelLayout, QoiError, QoiHeader, SinkDecoder,
+};
+
+/// Runs `data` through every decoder and checks that they agree on whether it is valid,
+/// the same way as the `decode` fuzz target.
+fn check_decoders(data: &[u8]) {
+    let decoded = try_decode(data);
+
+    let split = data
+        .first()
+        .map_or(0, |&byte| byte as usize)
+        .min(data.len());
+    let mut pixels = Vec::new();
+    let mut sink = SinkDecoder::new();
+    let mut put = |_, _, pixel: Pixel| pixels.push(pixel);
+    let pushed = sink
+        .push(&data[..split], &mut put)
+        .and_then(|_| sink.push(&data[split..], &mut put))
+        .and_then(|_| sink.finish());
+
+    let streamed = Decoder::new(data).and_then(|mut decoder| {
+        // the row is allocated by the caller, which would not pick a huge one
+        let width = decoder.header().width as usize;
+        if width > 1 << 16 {
+            return Err(QoiError::DimensionsTooLarge {
+                width: decoder.header().width,
+                height: decoder.header().height,
+            }
+            .into());
+        }
+        let mut row = vec![Pixel::default(); width];
+        let mut pixels = Vec::new();
+        while decoder.read_row(&mut row)? {
+            pixels.extend_from_slice(&row);
+        }
+        Ok(pixels)
+    });
+
+    match &decoded {
+        Ok(image) => {
+            assert_eq!(pushed.as_ref(), Ok(&image.header));
+            assert_eq!(pixels, image.pixels);
+            assert_eq!(streamed.as_ref().ok(), Some(&image.pixels));
+
+            let mut buffer = vec![0; image.pixels.len() * 4];
+            let stride = image.header.width as usize * 4;
+            assert_eq!(
+                decode_into(data, &mut buffer, stride, PixelLayout::RGBA).as_ref(),
+                Ok(&image.header)
+            );
+            assert_eq!(buffer, image.to_u8());
+        }
+        Err(_) => {
+            assert!(pushed.is_err());
+            assert!(streamed.is_err());
+        }
+    }
+}
+
+fn random_image() -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let width = rng.gen_range(1..10);
+    let height = rng.gen_range(1..10);
+    let pixels = (0..width * height)
+        .map(|_| Pixel {
+            a: [rng.gen(), 255][rng.gen_range(0..2)],
+            ..Pixel::random()
+        })
+        .collect::<Vec<_>>();
+    encode_from_pix(&pixels, width, height)
+}
+
+#[test]
+fn every_truncation() {
+    for _ in 0..20 {
+        let encoded = random_image();
+        for len in 0..encoded.len() {
+            assert!(try_decode(&encoded[..len]).is_err());
+            check_decoders(&encoded[..len]);
+        }
+    }
+}
+
+#[test]
+fn random_mutations() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..2000 {
+        let mut encoded = random_image();
+        for _ in 0..rng.gen_range(1..4) {
+            let i = rng.gen_range(0..encoded.len());
+            encoded[i] = rng.gen();
+        }
+        check_decoders(&encoded);
+    }
+}
+
+#[test]
+fn short_headers() {
+    for len in 0..14 {
+        assert_eq!(
+            QoiHeader::from_u8(&b"qoif\0\0\0\x01\0\0\0\x01\x04\0"[..len]),
+            Err(QoiError::Truncated { offset: len })
+        );
+    }
+}
+
+#[test]
+fn huge_dimensions_are_not_allocated() {
+    // 4 billion by 4 billion pixels in a few bytes
+    let mut encoded = encode_from_pix(&[Pixel::default()], 1, 1);
+    encoded[4..12].copy_from_slice(&[0xFF; 8]);
+
+    assert_eq!(
+        try_decode(&encoded),
+        Err(QoiError::LimitExceeded {
+            width: u32::MAX,
+            height: u32::MAX
+        })
+    );
+    // without limits, the stream is too short for that many pixels
+    assert_eq!(
+        try_decode_with_limits(&encoded, DecodeLimits::NONE),
+        Err(QoiError::Truncated {
+            offset: encoded.len()
+        })
+    );
+    check_decoders(&encoded);
+}
+
+#[test]
+fn empty_image_without_end_marker() {
+    // found by fuzzing: the stream decoder did not check the end marker if there were no rows
+    let mut encoded = encode_from_pix(&[], 521, 0);
+    encoded[14..].copy_from_slice(&[0, 0, 0, 1, 3, 0, 0, 0]);
+
+    assert!(try_decode(&encoded).is_err());
+    assert!(Decoder::new(&encoded[..]).is_err());
+    check_decoders(&encoded);
+}
@@ -0,0 +1,205 @@
+use rand::Rng;
+
+use crate::{
+    decode_into, decode_into_dithered, decode_to_pix, decode_to_u8, encode_from_pix,
+    encode_from_u8, Pixel, PixelLayout,
+};
+
+const LAYOUTS: [PixelLayout; 5] = [
+    PixelLayout::RGB,
+    PixelLayout::RGBA,
+    PixelLayout::BGR,
+    PixelLayout::BGRA,
+    PixelLayout::ARGB,
+];
+
+#[test]
+fn round_trip() {
+    let mut rng = rand::thread_rng();
+    for layout in LAYOUTS {
+        for _ in 0..100 {
+            let width = rng.gen_range(1..10);
+            let height = rng.gen_range(1..10);
+            let bytes = (0..width * height * layout.bytes_per_pixel())
+                .map(|_| rng.gen_range(0..4))
+                .collect::<Vec<u8>>();
+
+            let encoded = encode_from_u8(&bytes, width as u32, height as u32, layout);
+
+            assert_eq!(decode_to_u8(&encoded, layout), bytes);
+        }
+    }
+}
+
+#[test]
+fn byte_order() {
+    let pixel = Pixel {
+        r: 1,
+        g: 2,
+        b: 3,
+        a: 4,
+    };
+    let opaque = Pixel { a: 255, ..pixel };
+    let cases = [
+        (PixelLayout::RGB, &[1, 2, 3][..], opaque),
+        (PixelLayout::RGBA, &[1, 2, 3, 4][..], pixel),
+        (PixelLayout::BGR, &[3, 2, 1][..], opaque),
+        (PixelLayout::BGRA, &[3, 2, 1, 4][..], pixel),
+        (PixelLayout::ARGB, &[4, 1, 2, 3][..], pixel),
+    ];
+    for (layout, bytes, expected) in cases {
+        let encoded = encode_from_u8(bytes, 1, 1, layout);
+
+        assert_eq!(decode_to_pix(&encoded), [expected], "{:?}", layout);
+    }
+}
+
+#[test]
+fn convert_between_layouts() {
+    let bgra = [3, 2, 1, 4, 7, 6, 5, 8];
+    let encoded = encode_from_u8(&bgra, 2, 1, PixelLayout::BGRA);
+
+    assert_eq!(
+        decode_to_u8(&encoded, PixelLayout::RGBA),
+        [1, 2, 3, 4, 5, 6, 7, 8]
+    );
+    assert_eq!(decode_to_u8(&encoded, PixelLayout::RGB), [1, 2, 3, 5, 6, 7]);
+}
+
+#[test]
+fn packed_layouts() {
+    let pixel = Pixel {
+        r: 0b11111000,
+        g: 0b00000100,
+        b: 0b00001000,
+        a: 255,
+    };
+    let encoded = encode_from_pix(&[pixel], 1, 1);
+    let cases = [
+        (PixelLayout::RGB565LE, [0x21, 0xF8]),
+        (PixelLayout::RGB565BE, [0xF8, 0x21]),
+        (PixelLayout::RGB555LE, [0x01, 0x7C]),
+        (PixelLayout::RGB555BE, [0x7C, 0x01]),
+    ];
+    for (layout, bytes) in cases {
+        assert_eq!(decode_to_u8(&encoded, layout), bytes, "{:?}", layout);
+    }
+}
+
+#[test]
+fn packed_round_trip() {
+    // values that survive the conversion to fewer bits unchanged
+    let mut rng = rand::thread_rng();
+    for layout in [
+        PixelLayout::RGB565LE,
+        PixelLayout::RGB565BE,
+        PixelLayout::RGB555LE,
+        PixelLayout::RGB555BE,
+        PixelLayout::Luma,
+    ] {
+        for _ in 0..100 {
+            let width = rng.gen_range(1..10);
+            let height = rng.gen_range(1..10);
+            let bytes = (0..width * height * layout.bytes_per_pixel())
+                .map(|_| rng.gen_range(0..=255))
+                .collect::<Vec<u8>>();
+            let bytes = match layout {
+                // the unused top bit of the 555 layouts is written as zero
+                PixelLayout::RGB555LE => bytes
+                    .chunks(2)
+                    .flat_map(|chunk| [chunk[0], chunk[1] & 0x7F])
+                    .collect(),
+                PixelLayout::RGB555BE => bytes
+                    .chunks(2)
+                    .flat_map(|chunk| [chunk[0] & 0x7F, chunk[1]])
+                    .collect(),
+                _ => bytes,
+            };
+
+            let encoded = encode_from_u8(&bytes, width as u32, height as u32, layout);
+
+            assert_eq!(decode_to_u8(&encoded, layout), bytes, "{:?}", layout);
+        }
+    }
+}
+
+#[test]
+fn expand_to_full_range() {
+    let encoded = encode_from_u8(&[0xFF, 0xFF], 1, 1, PixelLayout::RGB565LE);
+    assert_eq!(decode_to_u8(&encoded, PixelLayout::RGB), [255, 255, 255]);
+
+    let encoded = encode_from_u8(&[0x00, 0x00], 1, 1, PixelLayout::RGB555BE);
+    assert_eq!(decode_to_u8(&encoded, PixelLayout::RGB), [0, 0, 0]);
+}
+
+#[test]
+fn luma() {
+    let pixels = [
+        Pixel::default(),
+        Pixel {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        },
+        Pixel {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        },
+        Pixel {
+            r: 0,
+            g: 255,
+            b: 0,
+            a: 255,
+        },
+        Pixel {
+            r: 0,
+            g: 0,
+            b: 255,
+            a: 255,
+        },
+    ];
+    let encoded = encode_from_pix(&pixels, 5, 1);
+
+    assert_eq!(
+        decode_to_u8(&encoded, PixelLayout::Luma),
+        [0, 255, 77, 149, 29]
+    );
+}
+
+#[test]
+fn dithering() {
+    // a flat color between two 565 steps should come out as a mix of both
+    let pixels = vec![
+        Pixel {
+            r: 0b1000_0100,
+            g: 0b1000_0010,
+            b: 0b1000_0100,
+            a: 255,
+        };
+        16
+    ];
+    let encoded = encode_from_pix(&pixels, 4, 4);
+
+    let mut plain = [0; 32];
+    decode_into(&encoded, &mut plain, 8, PixelLayout::RGB565BE).unwrap();
+    let mut dithered = [0; 32];
+    decode_into_dithered(&encoded, &mut dithered, 8, PixelLayout::RGB565BE).unwrap();
+
+    let red = |bytes: &[u8]| {
+        bytes
+            .chunks(2)
+            .map(|pixel| pixel[0] >> 3)
+            .collect::<Vec<_>>()
+    };
+    assert!(red(&plain).iter().all(|&r| r == 0b10000));
+    assert!(red(&dithered).contains(&0b10000));
+    assert!(red(&dithered).contains(&0b10001));
+
+    // layouts with 8 bits per channel are not dithered
+    let mut rgb = [0; 48];
+    decode_into_dithered(&encoded, &mut rgb, 12, PixelLayout::RGB).unwrap();
+    assert_eq!(rgb.to_vec(), decode_to_u8(&encoded, PixelLayout::RGB));
+}
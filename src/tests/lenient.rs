@@ -0,0 +1,127 @@
+use rand::Rng;
+
+use crate::{
+    decode_lenient, decode_lenient_with_limits, encode_from_pix, header, Channels, ColorSpace,
+    DecodeLimits, Pixel, QoiError, END_MARKER,
+};
+
+const FILL: Pixel = Pixel {
+    r: 255,
+    g: 0,
+    b: 255,
+    a: 255,
+};
+
+#[test]
+fn valid_stream() {
+    let pixels = (0..12).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 4, 3);
+
+    let partial = decode_lenient(&encoded, FILL).unwrap();
+
+    assert_eq!(partial.failure, None);
+    assert_eq!(partial.image.pixels, pixels);
+}
+
+#[test]
+fn every_truncation() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        let pixels = (0..width * height)
+            .map(|_| [Pixel::random(), Pixel::default()][rng.gen_range(0..2)])
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width, height);
+
+        let mut previous = 0;
+        for len in 14..encoded.len() {
+            let partial = decode_lenient(&encoded[..len], FILL).unwrap();
+            let failure = partial.failure.unwrap();
+            let decoded = failure.pixels_decoded;
+
+            assert!(failure.offset <= len);
+            assert!(decoded >= previous);
+            assert_eq!(partial.image.pixels[..decoded], pixels[..decoded]);
+            assert!(partial.image.pixels[decoded..]
+                .iter()
+                .all(|&pixel| pixel == FILL));
+            previous = decoded;
+        }
+    }
+}
+
+#[test]
+fn cut_off_in_op() {
+    let pixels = [
+        Pixel::default(),
+        Pixel {
+            r: 100,
+            g: 0,
+            b: 0,
+            a: 255,
+        },
+    ];
+    let encoded = encode_from_pix(&pixels, 2, 1);
+    // a run for the first pixel, then an OpRGB that is cut off after two of its four bytes
+    let partial = decode_lenient(&encoded[..17], FILL).unwrap();
+
+    assert_eq!(partial.image.pixels, [Pixel::default(), FILL]);
+    let failure = partial.failure.unwrap();
+    assert_eq!(failure.offset, 15);
+    assert_eq!(failure.error, QoiError::Truncated { offset: 17 });
+    assert_eq!(failure.pixels_decoded, 1);
+}
+
+#[test]
+fn missing_end_marker() {
+    let pixels = (0..6).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let mut encoded = encode_from_pix(&pixels, 3, 2);
+    let end = encoded.len() - 8;
+    encoded.truncate(end);
+
+    let partial = decode_lenient(&encoded, FILL).unwrap();
+
+    assert_eq!(partial.image.pixels, pixels);
+    let failure = partial.failure.unwrap();
+    assert_eq!(failure.offset, end);
+    assert_eq!(failure.error, QoiError::MissingEndMarker);
+    assert_eq!(failure.pixels_decoded, 6);
+}
+
+#[test]
+fn header_errors() {
+    let encoded = encode_from_pix(&[Pixel::default()], 1, 1);
+
+    assert_eq!(
+        decode_lenient(&encoded[..10], FILL),
+        Err(QoiError::Truncated { offset: 10 })
+    );
+    let limits = DecodeLimits {
+        max_pixels: 0,
+        ..DecodeLimits::default()
+    };
+    assert_eq!(
+        decode_lenient_with_limits(&encoded, FILL, limits),
+        Err(QoiError::LimitExceeded {
+            width: 1,
+            height: 1
+        })
+    );
+}
+
+#[test]
+fn huge_header() {
+    // would be 1.6 GB of pixels, almost all of them fill
+    let mut encoded = header(20_000, 20_000, Channels::RGB, ColorSpace::SRGB).to_vec();
+    encoded.push(0b11_111101);
+    encoded.extend_from_slice(&END_MARKER);
+
+    assert_eq!(
+        decode_lenient(&encoded, FILL),
+        Err(QoiError::LimitExceeded {
+            width: 20_000,
+            height: 20_000
+        })
+    );
+}
@@ -0,0 +1,109 @@
+use super::qoi_error;
+use crate::{
+    encode_from_pix, try_decode, try_decode_with_limits, DecodeLimits, Decoder, Pixel, QoiError,
+    SinkDecoder,
+};
+
+/// A valid stream for a `width` by `height` image of a single color.
+fn flat(width: u32, height: u32) -> Vec<u8> {
+    encode_from_pix(
+        &vec![Pixel::default(); width as usize * height as usize],
+        width,
+        height,
+    )
+}
+
+/// A header claiming `width` by `height` pixels, followed by an image of a single pixel.
+fn bomb(width: u32, height: u32) -> Vec<u8> {
+    let mut encoded = flat(1, 1);
+    encoded[4..8].copy_from_slice(&width.to_be_bytes());
+    encoded[8..12].copy_from_slice(&height.to_be_bytes());
+    encoded
+}
+
+#[test]
+fn default_is_spec_maximum() {
+    let limits = DecodeLimits::default();
+    assert_eq!(limits.max_pixels, 400_000_000);
+
+    let encoded = bomb(20_000, 20_001);
+    let exceeded = QoiError::LimitExceeded {
+        width: 20_000,
+        height: 20_001,
+    };
+    assert_eq!(try_decode(&encoded), Err(exceeded.clone()));
+    assert_eq!(
+        qoi_error(Decoder::new(&encoded[..]).err().unwrap()),
+        exceeded
+    );
+    let mut sink = SinkDecoder::new();
+    assert_eq!(sink.push(&encoded, &mut |_, _, _| {}), Err(exceeded));
+
+    // exactly at the limit, the stream is only too short
+    assert_eq!(
+        try_decode(&bomb(20_000, 20_000)),
+        Err(QoiError::Truncated { offset: 23 })
+    );
+}
+
+#[test]
+fn every_limit() {
+    let encoded = flat(8, 4);
+    let cases = [
+        DecodeLimits {
+            max_width: 7,
+            ..DecodeLimits::NONE
+        },
+        DecodeLimits {
+            max_height: 3,
+            ..DecodeLimits::NONE
+        },
+        DecodeLimits {
+            max_pixels: 31,
+            ..DecodeLimits::NONE
+        },
+        DecodeLimits {
+            max_alloc_bytes: 127,
+            ..DecodeLimits::NONE
+        },
+    ];
+    for limits in cases {
+        assert_eq!(
+            try_decode_with_limits(&encoded, limits),
+            Err(QoiError::LimitExceeded {
+                width: 8,
+                height: 4
+            }),
+            "{:?}",
+            limits
+        );
+    }
+
+    let limits = DecodeLimits {
+        max_width: 8,
+        max_height: 4,
+        max_pixels: 32,
+        max_alloc_bytes: 128,
+    };
+    assert_eq!(
+        try_decode_with_limits(&encoded, limits)
+            .unwrap()
+            .pixels
+            .len(),
+        32
+    );
+}
+
+#[test]
+fn alloc_limit_only_for_allocating_decoders() {
+    let encoded = flat(8, 4);
+    let limits = DecodeLimits {
+        max_alloc_bytes: 0,
+        ..DecodeLimits::default()
+    };
+
+    assert!(Decoder::with_limits(&encoded[..], limits).is_ok());
+    let mut sink = SinkDecoder::with_limits(limits);
+    sink.push(&encoded, &mut |_, _, _| {}).unwrap();
+    assert!(sink.finish().is_ok());
+}
@@ -1,8 +1,59 @@
 #[cfg(test)]
+mod asm;
+#[cfg(test)]
+mod conformance;
+#[cfg(test)]
+mod decode_into;
+#[cfg(all(test, feature = "std"))]
+mod decoder;
+#[cfg(test)]
+mod encode_into;
+#[cfg(all(test, feature = "std"))]
+mod encoder;
+#[cfg(test)]
+mod heatmap;
+#[cfg(all(test, feature = "std"))]
+mod hostile;
+#[cfg(test)]
+mod layout;
+#[cfg(test)]
+mod lenient;
+#[cfg(all(test, feature = "std"))]
+mod limits;
+#[cfg(test)]
+mod op;
+#[cfg(test)]
+mod recompress;
+#[cfg(test)]
+mod reference;
+#[cfg(test)]
+mod run;
+#[cfg(test)]
+mod sink;
+#[cfg(test)]
+mod stats;
+#[cfg(test)]
+mod validate;
+
+/// Extracts the [`QoiError`](crate::QoiError) from an error returned by the streaming API.
+#[cfg(all(test, feature = "std"))]
+fn qoi_error(err: std::io::Error) -> crate::QoiError {
+    err.into_inner()
+        .unwrap()
+        .downcast_ref::<crate::QoiError>()
+        .unwrap()
+        .clone()
+}
+
+#[allow(clippy::module_inception)]
+#[cfg(test)]
 mod tests {
     use rand::Rng;
 
-    use crate::{decode_to_pix, encode_from_pix, Pixel};
+    use crate::{
+        decode, decode_to_pix, encode_from_pix, encode_from_u8, try_decode, try_encode, Channels,
+        Pixel, PixelLayout, QoiError, QoiHeader,
+    };
 
     #[test]
     fn constant8x8() {
@@ -101,4 +152,234 @@ mod tests {
             );
         }
     }
+
+    fn random_rgba(width: usize, height: usize) {
+        let mut rng = rand::thread_rng();
+        let pixels = (0..width * height)
+            .map(|_| Pixel {
+                a: rng.gen(),
+                ..Pixel::random()
+            })
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+
+        let decoded = decode_to_pix(&encoded);
+
+        assert_eq!(pixels, decoded);
+    }
+
+    #[test]
+    fn repeated_random_rgba_x_by_x() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            random_rgba(rng.gen_range(1..10), rng.gen_range(1..10));
+        }
+    }
+
+    #[test]
+    fn alpha_steps8x8() {
+        let width = 8;
+        let height = 8;
+        // only alpha changes, so every other op has to carry it over
+        let pixels = (0..width * height)
+            .map(|i| Pixel {
+                r: 10,
+                g: 20,
+                b: 30,
+                a: (i / 4 * 16) as u8,
+            })
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+
+        let decoded = decode_to_pix(&encoded);
+
+        assert_eq!(pixels, decoded);
+    }
+
+    #[test]
+    fn transparent_black_starts_in_hash() {
+        // the hash starts out zeroed, so (0, 0, 0, 0) is an OpIndex right away
+        let pixels = vec![
+            Pixel {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 0,
+            },
+            Pixel::default(),
+            Pixel {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 0,
+            },
+        ];
+        let encoded = encode_from_pix(&pixels, 3, 1);
+
+        assert_eq!(
+            &encoded[14..21],
+            &[0b00000000, 0b11111111, 0, 0, 0, 255, 0b00000000]
+        );
+        assert_eq!(pixels, decode_to_pix(&encoded));
+    }
+
+    #[test]
+    fn every_op_updates_hash() {
+        // like the reference decoder, a run of the initial pixel puts it into the hash,
+        // so the index it hashes to (53) refers to it afterwards
+        let mut encoded = crate::header(2, 1, Channels::RGB, crate::ColorSpace::SRGB).to_vec();
+        encoded.extend_from_slice(&[0b11000000, 53]);
+        encoded.extend_from_slice(&crate::END_MARKER);
+
+        assert_eq!(decode_to_pix(&encoded), vec![Pixel::default(); 2]);
+        let mut sink = vec![];
+        crate::decode_to_sink(&encoded, &mut |_, _, pixel| sink.push(pixel)).unwrap();
+        assert_eq!(sink, vec![Pixel::default(); 2]);
+    }
+
+    #[test]
+    fn header_channels() {
+        let opaque = vec![Pixel::default(); 4];
+        let encoded = encode_from_pix(&opaque, 2, 2);
+        let header = QoiHeader::from_u8(&encoded).unwrap();
+        assert_eq!(header.channels, Channels::RGB);
+
+        let mut translucent = opaque;
+        translucent[3].a = 128;
+        let encoded = encode_from_pix(&translucent, 2, 2);
+        let header = QoiHeader::from_u8(&encoded).unwrap();
+        assert_eq!(header.channels, Channels::RGBA);
+    }
+
+    #[test]
+    fn rgba_bytes_keep_alpha() {
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 255, 9, 10, 11, 0, 9, 10, 11, 0];
+        let encoded = encode_from_u8(&bytes, 2, 2, PixelLayout::RGBA);
+
+        assert_eq!(crate::decode_to_u8(&encoded, PixelLayout::RGBA), bytes);
+    }
+
+    #[test]
+    fn end_marker() {
+        let pixels = vec![Pixel::default(); 3];
+        let encoded = encode_from_pix(&pixels, 3, 1);
+
+        // a single run followed by the end marker
+        assert_eq!(&encoded[14..], &[0b11000010, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(pixels, decode_to_pix(&encoded));
+    }
+
+    #[test]
+    #[should_panic(expected = "missing or malformed end marker")]
+    fn missing_end_marker() {
+        let pixels = (0..16).map(|_| Pixel::random()).collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, 4, 4);
+
+        decode_to_pix(&encoded[..encoded.len() - 8]);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing or malformed end marker")]
+    fn malformed_end_marker() {
+        let pixels = vec![Pixel::default(); 16];
+        let mut encoded = encode_from_pix(&pixels, 4, 4);
+        let len = encoded.len();
+        encoded[len - 1] = 0;
+
+        decode_to_pix(&encoded);
+    }
+
+    #[test]
+    fn invalid_headers() {
+        let pixels = vec![Pixel::default(); 4];
+        let encoded = encode_from_pix(&pixels, 2, 2);
+
+        assert_eq!(
+            try_decode(&encoded[..10]),
+            Err(QoiError::Truncated { offset: 10 })
+        );
+
+        let mut bad_magic = encoded.clone();
+        bad_magic[0] = b'p';
+        assert_eq!(
+            try_decode(&bad_magic),
+            Err(QoiError::InvalidMagic(*b"poif"))
+        );
+
+        let mut bad_channels = encoded.clone();
+        bad_channels[12] = 5;
+        assert_eq!(try_decode(&bad_channels), Err(QoiError::InvalidChannels(5)));
+
+        let mut bad_color_space = encoded;
+        bad_color_space[13] = 2;
+        assert_eq!(
+            try_decode(&bad_color_space),
+            Err(QoiError::InvalidColorSpace(2))
+        );
+    }
+
+    #[test]
+    fn truncated_op() {
+        let mut encoded = encode_from_pix(&[Pixel::default()], 1, 1);
+        // replace everything after the header with the first two bytes of an OpRGB
+        encoded.splice(14.., [0b11111110, 42]);
+
+        assert_eq!(
+            try_decode(&encoded),
+            Err(QoiError::Truncated { offset: 16 })
+        );
+    }
+
+    #[test]
+    fn pixel_count_mismatch() {
+        let pixels = vec![Pixel::default(); 5];
+
+        assert_eq!(
+            try_encode(&pixels, 2, 2),
+            Err(QoiError::PixelCountMismatch {
+                expected: 4,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn decode_returns_header() {
+        let pixels = (0..6).map(|_| Pixel::random()).collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, 3, 2);
+
+        let image = decode(&encoded);
+
+        assert_eq!(image.header, QoiHeader::from_u8(&encoded).unwrap());
+        assert_eq!((image.header.width, image.header.height), (3, 2));
+        assert_eq!(image.pixels, pixels);
+    }
+
+    #[test]
+    fn run_is_clamped_to_image() {
+        let mut encoded = encode_from_pix(&[Pixel::default(); 2], 2, 1);
+        // a run of 62 pixels in a 2 pixel image
+        encoded[14] = 0b11111101;
+
+        assert_eq!(decode_to_pix(&encoded), vec![Pixel::default(); 2]);
+    }
+
+    #[test]
+    fn padding_is_not_decoded() {
+        let pixels = (0..9).map(|_| Pixel::random()).collect::<Vec<_>>();
+        let mut encoded = encode_from_pix(&pixels, 3, 3);
+        // trailing data after the end marker is ignored
+        encoded.extend_from_slice(&[0, 0, 0, 0]);
+
+        assert_eq!(decode_to_pix(&encoded), pixels);
+    }
+
+    #[test]
+    fn too_few_pixels() {
+        let mut encoded = encode_from_pix(&[Pixel::default(); 4], 2, 2);
+        // claim there are more rows than encoded
+        encoded[11] = 3;
+
+        assert_eq!(try_decode(&encoded), Err(QoiError::MissingEndMarker));
+    }
 }
@@ -0,0 +1,156 @@
+use rand::Rng;
+
+use crate::{encode_from_pix, header, tokenize, Channels, ColorSpace, Op, Pixel, QoiError};
+
+#[test]
+fn every_op() {
+    let mut encoded = header(7, 1, Channels::RGBA, ColorSpace::SRGB).to_vec();
+    let ops: [&[u8]; 6] = [
+        &[0b11111110, 10, 20, 30],
+        &[0b11111111, 10, 20, 30, 40],
+        &[0b00_000101],
+        &[0b01_00_01_11],
+        &[0b10_000000, 0b1111_0000],
+        &[0b11_000001],
+    ];
+    encoded.extend(ops.concat());
+    encoded.extend_from_slice(&crate::END_MARKER);
+
+    let ops = tokenize(&encoded).collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(
+        ops,
+        [
+            (
+                14,
+                Op::RGB {
+                    r: 10,
+                    g: 20,
+                    b: 30
+                }
+            ),
+            (
+                18,
+                Op::RGBA {
+                    r: 10,
+                    g: 20,
+                    b: 30,
+                    a: 40
+                }
+            ),
+            (23, Op::Index(5)),
+            (
+                24,
+                Op::Diff {
+                    dr: -2,
+                    dg: -1,
+                    db: 1
+                }
+            ),
+            (
+                25,
+                Op::Luma {
+                    dg: -32,
+                    dr_dg: 7,
+                    db_dg: -8
+                }
+            ),
+            (27, Op::Run(2)),
+        ]
+    );
+}
+
+#[test]
+fn covers_the_whole_stream() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        let pixels = (0..width * height)
+            .map(|_| [Pixel::random(), Pixel::default()][rng.gen_range(0..2)])
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width, height);
+
+        let mut offset = 14;
+        let mut num_pixels = 0;
+        for token in tokenize(&encoded) {
+            let (start, op) = token.unwrap();
+            assert_eq!(start, offset);
+            assert_eq!(Op::parse(&encoded[start..]), Some(op));
+            offset += op.size();
+            num_pixels += op.pixels();
+        }
+        assert_eq!(offset, encoded.len() - 8);
+        assert_eq!(num_pixels, pixels.len());
+    }
+}
+
+#[test]
+fn errors_end_the_iteration() {
+    let pixels = (0..4).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 2, 2);
+
+    let last = tokenize(&encoded[..encoded.len() - 10]).last().unwrap();
+    assert_eq!(
+        last,
+        Err(QoiError::Truncated {
+            offset: encoded.len() - 10
+        })
+    );
+
+    let last = tokenize(&encoded[..encoded.len() - 1]).last().unwrap();
+    assert_eq!(last, Err(QoiError::MissingEndMarker));
+
+    let mut tokens = tokenize(&encoded[1..]);
+    assert!(matches!(
+        tokens.next(),
+        Some(Err(QoiError::InvalidMagic(_)))
+    ));
+    assert_eq!(tokens.next(), None);
+}
+
+#[test]
+fn append_to_round_trips() {
+    let pixels = (0..64)
+        .map(|i| [Pixel::random(), Pixel::default()][i % 3 / 2])
+        .collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 8, 8);
+
+    let mut appended = encoded[..14].to_vec();
+    for token in tokenize(&encoded) {
+        let (_, op) = token.unwrap();
+        assert!(op.is_valid());
+        op.append_to(&mut appended).unwrap();
+    }
+    appended.extend_from_slice(&crate::END_MARKER);
+    assert_eq!(appended, encoded);
+}
+
+#[test]
+fn append_to_checks_ranges() {
+    let invalid = [
+        Op::Index(64),
+        Op::Diff {
+            dr: 2,
+            dg: 0,
+            db: 0,
+        },
+        Op::Luma {
+            dg: 40,
+            dr_dg: 0,
+            db_dg: 0,
+        },
+        Op::Luma {
+            dg: 0,
+            dr_dg: -9,
+            db_dg: 0,
+        },
+        Op::Run(0),
+        Op::Run(63),
+    ];
+    for op in invalid {
+        let mut encoded = vec![];
+        assert_eq!(op.append_to(&mut encoded), Err(QoiError::InvalidOp(op)));
+        assert!(encoded.is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{
+    assemble, decode, encode_from_pix, try_recompress, Channels, ColorSpace, Pixel, QoiError,
+    QoiHeader,
+};
+
+#[test]
+fn shrinks_and_keeps_header() {
+    // opaque, but the header still says RGBA
+    let encoded = assemble(
+        "
+        HEADER 4 1 RGBA LINEAR
+        RGBA 1 2 3 255
+        RGB 1 2 3
+        RUN 1
+        RUN 1
+        END
+        ",
+    )
+    .unwrap();
+
+    let recompressed = try_recompress(&encoded).unwrap();
+
+    assert!(recompressed.len() < encoded.len());
+    let header = QoiHeader::from_u8(&recompressed).unwrap();
+    assert_eq!(header.channels, Channels::RGBA);
+    assert_eq!(header.color_space, ColorSpace::Linear);
+    assert_eq!(decode(&recompressed).pixels, decode(&encoded).pixels);
+}
+
+#[test]
+fn keeps_smaller_original() {
+    let pixels = (0..16).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 4, 4);
+    assert_eq!(try_recompress(&encoded).unwrap(), encoded);
+
+    // the initial pixel is in the hash after a run of it, which our encoder does not make use of
+    let encoded = assemble("HEADER 3 1 RGB SRGB\nRUN 1\nRGB 100 200 50\nINDEX 53\nEND").unwrap();
+    assert_eq!(try_recompress(&encoded).unwrap(), encoded);
+}
+
+#[test]
+fn invalid_stream() {
+    let pixels = (0..4).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 2, 2);
+
+    assert_eq!(
+        try_recompress(&encoded[..encoded.len() - 1]),
+        Err(QoiError::MissingEndMarker)
+    );
+}
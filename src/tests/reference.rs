@@ -0,0 +1,182 @@
+use qoi_reference::Desc;
+use rand::Rng;
+
+use crate::{decode_to_pix, encode_from_pix, encode_reference, Channels, ColorSpace, Pixel};
+
+/// Pixels from a small random palette with random alpha, runs and small differences,
+/// so every op shows up.
+fn random_pixels(len: usize) -> Vec<Pixel> {
+    let mut rng = rand::thread_rng();
+    let palette = (0..rng.gen_range(1..80))
+        .map(|_| Pixel {
+            a: [0, 255, rng.gen()][rng.gen_range(0..3)],
+            ..Pixel::random()
+        })
+        .collect::<Vec<_>>();
+    let mut pixels = Vec::with_capacity(len);
+    let mut previous = Pixel::default();
+    while pixels.len() < len {
+        let pixel = match rng.gen_range(0..4) {
+            0 => previous,
+            1 => palette[rng.gen_range(0..palette.len())],
+            2 => Pixel {
+                r: previous
+                    .r
+                    .wrapping_add(rng.gen_range(0..40))
+                    .wrapping_sub(20),
+                g: previous
+                    .g
+                    .wrapping_add(rng.gen_range(0..70))
+                    .wrapping_sub(35),
+                b: previous
+                    .b
+                    .wrapping_add(rng.gen_range(0..40))
+                    .wrapping_sub(20),
+                a: previous.a,
+            },
+            _ => Pixel {
+                a: rng.gen(),
+                ..Pixel::random()
+            },
+        };
+        let run = rng.gen_range(1..100).min(len - pixels.len());
+        pixels.extend((0..run).map(|_| pixel));
+        previous = pixel;
+    }
+    pixels
+}
+
+fn to_rgba(pixels: &[Pixel]) -> Vec<u8> {
+    pixels
+        .iter()
+        .flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a])
+        .collect()
+}
+
+fn desc(width: usize, height: usize, channels: u8) -> Desc {
+    Desc {
+        width: width as u32,
+        height: height as u32,
+        channels,
+        colorspace: 0,
+    }
+}
+
+#[test]
+fn same_encoding() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..500 {
+        let width = rng.gen_range(1..40);
+        let height = rng.gen_range(1..40);
+        let pixels = random_pixels(width * height);
+        let rgba = to_rgba(&pixels);
+
+        let expected = qoi_reference::encode(&rgba, desc(width, height, 4)).unwrap();
+
+        let encoded = encode_reference(
+            &rgba,
+            width as u32,
+            height as u32,
+            Channels::RGBA,
+            ColorSpace::SRGB,
+        )
+        .unwrap();
+        assert_eq!(encoded, expected);
+        // the default encoder only differs in the channels it picks for the header
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+        assert_eq!(encoded[..12], expected[..12]);
+        assert_eq!(encoded[13..], expected[13..]);
+    }
+}
+
+#[test]
+fn same_encoding_rgb() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..200 {
+        let width = rng.gen_range(1..40);
+        let height = rng.gen_range(1..40);
+        let rgb = random_pixels(width * height)
+            .iter()
+            .flat_map(|pixel| [pixel.r, pixel.g, pixel.b])
+            .collect::<Vec<_>>();
+
+        let expected = qoi_reference::encode(&rgb, desc(width, height, 3)).unwrap();
+
+        let encoded = encode_reference(
+            &rgb,
+            width as u32,
+            height as u32,
+            Channels::RGB,
+            ColorSpace::SRGB,
+        )
+        .unwrap();
+        assert_eq!(encoded, expected);
+    }
+}
+
+#[test]
+fn decode_each_other() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..500 {
+        let width = rng.gen_range(1..40);
+        let height = rng.gen_range(1..40);
+        let pixels = random_pixels(width * height);
+        let rgba = to_rgba(&pixels);
+
+        let theirs = qoi_reference::encode(&rgba, desc(width, height, 4)).unwrap();
+        assert_eq!(decode_to_pix(&theirs), pixels);
+
+        let ours = encode_from_pix(&pixels, width as u32, height as u32);
+        let (desc, decoded) = qoi_reference::decode(&ours, 4).unwrap();
+        assert_eq!((desc.width as usize, desc.height as usize), (width, height));
+        assert_eq!(decoded, rgba);
+    }
+}
+
+#[test]
+fn decode_random_ops() {
+    // streams no encoder would write, like index ops pointing at unused slots
+    // or runs of the initial pixel, have to decode the same way
+    let mut rng = rand::thread_rng();
+    for _ in 0..500 {
+        let width = rng.gen_range(1..40);
+        let height = rng.gen_range(1..40);
+        let mut encoded = crate::header(
+            width as u32,
+            height as u32,
+            Channels::RGBA,
+            ColorSpace::SRGB,
+        )
+        .to_vec();
+        let mut decoded = 0;
+        while decoded < width * height {
+            let tag: u8 = rng.gen();
+            encoded.push(tag);
+            let size = crate::Chunk::size_from_tag(tag);
+            encoded.extend((1..size).map(|_| rng.gen::<u8>()));
+            decoded += match tag {
+                0b11111110 | 0b11111111 => 1,
+                _ if tag >> 6 == 0b11 => (tag & 0b00111111) as usize + 1,
+                _ => 1,
+            };
+        }
+        encoded.extend_from_slice(&crate::END_MARKER);
+
+        let (_, expected) = qoi_reference::decode(&encoded, 4).unwrap();
+
+        assert_eq!(to_rgba(&decode_to_pix(&encoded)), expected);
+    }
+}
+
+#[test]
+fn decode_hash_updates() {
+    // qoi.h puts the pixel of every op into the hash, so a run of the initial pixel
+    // makes index 53 refer to it
+    let mut encoded = crate::header(3, 1, Channels::RGBA, ColorSpace::SRGB).to_vec();
+    encoded.extend_from_slice(&[0b11_000000, 0b11111110, 1, 2, 3, 0b00_110101]);
+    encoded.extend_from_slice(&crate::END_MARKER);
+
+    let (_, expected) = qoi_reference::decode(&encoded, 4).unwrap();
+
+    assert_eq!(to_rgba(&decode_to_pix(&encoded)), expected);
+}
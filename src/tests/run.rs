@@ -0,0 +1,69 @@
+use rand::Rng;
+
+use crate::{encode_from_pix, encode_from_pix_scalar, encode_into, run, Pixel};
+
+/// Pixels from a small palette, so there are plenty of runs of different lengths.
+fn random_pixels(len: usize) -> Vec<Pixel> {
+    let mut rng = rand::thread_rng();
+    let palette = [Pixel::random(), Pixel::random(), Pixel::default()];
+    let mut pixels = Vec::with_capacity(len);
+    while pixels.len() < len {
+        let run = rng.gen_range(1..200).min(len - pixels.len());
+        let pixel = palette[rng.gen_range(0..palette.len())];
+        pixels.extend((0..run).map(|_| pixel));
+    }
+    pixels
+}
+
+#[test]
+fn implementations_agree() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..1000 {
+        let pixels = random_pixels(rng.gen_range(0..100));
+        let start = rng.gen_range(0..=pixels.len());
+        let pixel = pixels.get(start).copied().unwrap_or_default();
+
+        let expected = run::scalar(&pixels[start..], pixel);
+        for run_length in run::available() {
+            assert_eq!(run_length(&pixels[start..], pixel), expected);
+        }
+    }
+}
+
+#[test]
+fn same_output_as_scalar() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let width = rng.gen_range(1..100);
+        let height = rng.gen_range(1..100);
+        let pixels = random_pixels(width * height);
+
+        let expected = encode_from_pix_scalar(&pixels, width as u32, height as u32);
+
+        assert_eq!(
+            encode_from_pix(&pixels, width as u32, height as u32),
+            expected
+        );
+        let mut buffer = vec![0; expected.len()];
+        assert_eq!(
+            encode_into(&pixels, width as u32, height as u32, &mut buffer),
+            Ok(expected.len())
+        );
+        assert_eq!(buffer, expected);
+    }
+}
+
+#[test]
+fn long_runs() {
+    // runs that are split up into several ops, with and without a remainder
+    for len in [61, 62, 63, 124, 125, 1000] {
+        let pixels = vec![Pixel::default(); len];
+
+        assert_eq!(
+            encode_from_pix(&pixels, len as u32, 1),
+            encode_from_pix_scalar(&pixels, len as u32, 1),
+            "{}",
+            len
+        );
+    }
+}
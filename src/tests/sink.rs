@@ -0,0 +1,164 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use rand::Rng;
+
+use crate::{decode_to_pix, decode_to_sink, encode_from_pix, Pixel, QoiError, SinkDecoder};
+
+/// Counts the allocations made by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn random_image(rng: &mut impl Rng) -> (Vec<Pixel>, usize, usize) {
+    let width = rng.gen_range(1..10);
+    let height = rng.gen_range(1..10);
+    // few colors, so that there are runs across rows
+    let pixel_list = (0..3)
+        .map(|_| Pixel {
+            a: rng.gen(),
+            ..Pixel::random()
+        })
+        .collect::<Vec<_>>();
+    let pixels = (0..width * height)
+        .map(|_| pixel_list[rng.gen_range(0..pixel_list.len())])
+        .collect::<Vec<_>>();
+    (pixels, width, height)
+}
+
+#[test]
+fn matches_decode_to_pix() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..1_000 {
+        let (pixels, width, height) = random_image(&mut rng);
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+
+        let mut decoded = vec![Pixel::default(); width * height];
+        let mut next = 0;
+        let header = decode_to_sink(&encoded, &mut |x: u32, y: u32, pixel| {
+            // pixels arrive in order
+            assert_eq!(y as usize * width + x as usize, next);
+            next += 1;
+            decoded[y as usize * width + x as usize] = pixel;
+        })
+        .unwrap();
+
+        assert_eq!((header.width, header.height), (width as u32, height as u32));
+        assert_eq!(decoded, decode_to_pix(&encoded));
+    }
+}
+
+#[test]
+fn pushed_in_pieces() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..1_000 {
+        let (pixels, width, height) = random_image(&mut rng);
+        let encoded = encode_from_pix(&pixels, width as u32, height as u32);
+
+        let mut decoded = Vec::new();
+        let mut decoder = SinkDecoder::new();
+        let mut input = &encoded[..];
+        while !input.is_empty() {
+            let (piece, rest) = input.split_at(rng.gen_range(1..8).min(input.len()));
+            decoder
+                .push(piece, &mut |_, _, pixel| decoded.push(pixel))
+                .unwrap();
+            input = rest;
+        }
+        decoder.finish().unwrap();
+
+        assert_eq!(decoded, pixels);
+    }
+}
+
+#[test]
+fn does_not_allocate() {
+    let pixels = (0..64 * 64)
+        .map(|i| Pixel {
+            r: (i % 7) as u8 * 30,
+            g: (i / 64) as u8,
+            b: (i % 64) as u8,
+            a: 255,
+        })
+        .collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 64, 64);
+
+    let mut checksum = 0u32;
+    let before = ALLOCATIONS.with(Cell::get);
+    let mut decoder = SinkDecoder::new();
+    for piece in encoded.chunks(3) {
+        decoder
+            .push(piece, &mut |x: u32, y: u32, pixel: Pixel| {
+                checksum = checksum.wrapping_add(x ^ y ^ pixel.r as u32)
+            })
+            .unwrap();
+    }
+    decoder.finish().unwrap();
+    let after = ALLOCATIONS.with(Cell::get);
+
+    assert_eq!(before, after);
+    assert_ne!(checksum, 0);
+
+    // make sure allocations are actually counted
+    std::hint::black_box(vec![0u8; 16]);
+    assert!(ALLOCATIONS.with(Cell::get) > after);
+}
+
+#[test]
+fn truncated() {
+    let pixels = (0..4).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 4, 1);
+    let truncated = &encoded[..encoded.len() - 10];
+
+    assert_eq!(
+        decode_to_sink(truncated, &mut |_, _, _| {}),
+        Err(QoiError::Truncated {
+            offset: truncated.len()
+        })
+    );
+    assert_eq!(
+        decode_to_sink(&encoded[..10], &mut |_, _, _| {}),
+        Err(QoiError::Truncated { offset: 10 })
+    );
+}
+
+#[test]
+fn end_marker() {
+    let encoded = encode_from_pix(&[Pixel::default(); 4], 2, 2);
+
+    assert_eq!(
+        decode_to_sink(&encoded[..encoded.len() - 1], &mut |_, _, _| {}),
+        Err(QoiError::MissingEndMarker)
+    );
+
+    let mut malformed = encoded.clone();
+    let len = malformed.len();
+    malformed[len - 1] = 2;
+    assert_eq!(
+        decode_to_sink(&malformed, &mut |_, _, _| {}),
+        Err(QoiError::MissingEndMarker)
+    );
+
+    // trailing data after the end marker is ignored
+    let mut trailing = encoded;
+    trailing.extend_from_slice(&[1, 2, 3]);
+    assert!(decode_to_sink(&trailing, &mut |_, _, _| {}).is_ok());
+}
@@ -0,0 +1,67 @@
+use crate::{
+    analyze, assemble, encode_from_pix, encode_with_stats, Channels, OpStats, Pixel, QoiError,
+};
+
+#[test]
+fn counts_every_op() {
+    let encoded = assemble(
+        "
+        HEADER 10 10 RGB SRGB
+        RGB 100 0 0
+        RGBA 100 0 0 0
+        RGBA 100 0 0 255
+        INDEX 53
+        INDEX 53
+        DIFF 1 1 1
+        LUMA 10 0 0
+        RUN 62
+        RUN 30
+        RUN 1
+        END
+        ",
+    )
+    .unwrap();
+
+    let stats = analyze(&encoded).unwrap();
+
+    let op = |count, bytes| OpStats { count, bytes };
+    assert_eq!(stats.rgb, op(1, 4));
+    assert_eq!(stats.rgba, op(2, 10));
+    assert_eq!(stats.index, op(2, 2));
+    assert_eq!(stats.diff, op(1, 1));
+    assert_eq!(stats.luma, op(1, 2));
+    assert_eq!(stats.run, op(3, 3));
+    let mut run_lengths = [0; 63];
+    run_lengths[1] = 1;
+    run_lengths[30] = 1;
+    run_lengths[62] = 1;
+    assert_eq!(stats.run_lengths, run_lengths);
+
+    assert_eq!(stats.encoded_len, 14 + 22 + 8);
+    assert_eq!(stats.hash_hit_rate(), 2.0 / 7.0);
+    assert_eq!(stats.bytes_per_pixel(), 0.44);
+    assert_eq!(stats.compression_ratio(Channels::RGBA), 400.0 / 44.0);
+}
+
+#[test]
+fn matches_encoded() {
+    let pixels = (0..64)
+        .map(|i| [Pixel::random(), Pixel::default()][i % 3 / 2])
+        .collect::<Vec<_>>();
+
+    let (encoded, stats) = encode_with_stats(&pixels, 8, 8).unwrap();
+
+    assert_eq!(encoded, encode_from_pix(&pixels, 8, 8));
+    assert_eq!(stats, analyze(&encoded).unwrap());
+    assert_eq!(stats.encoded_len, encoded.len());
+}
+
+#[test]
+fn invalid_stream() {
+    let encoded = encode_from_pix(&[Pixel::default(); 4], 2, 2);
+
+    assert_eq!(
+        analyze(&encoded[..encoded.len() - 1]),
+        Err(QoiError::MissingEndMarker)
+    );
+}
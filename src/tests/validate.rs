@@ -0,0 +1,136 @@
+use rand::Rng;
+
+use crate::{
+    assemble, encode_from_pix, try_decode, validate, DecodeFailure, Op, Pixel, QoiError, Warning,
+    WarningKind,
+};
+
+#[test]
+fn own_output_is_canonical() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let width = rng.gen_range(1..10);
+        let height = rng.gen_range(1..10);
+        let pixels = (0..width * height)
+            .map(|_| [Pixel::random(), Pixel::default()][rng.gen_range(0..2)])
+            .collect::<Vec<_>>();
+        let encoded = encode_from_pix(&pixels, width, height);
+
+        let report = validate(&encoded);
+        assert!(report.is_canonical(), "{:?}", report);
+    }
+}
+
+#[test]
+fn leading_run_is_not_in_the_encoder_hash() {
+    let red = Pixel {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    let encoded = encode_from_pix(&[Pixel::default(), red, Pixel::default()], 3, 1);
+
+    assert!(validate(&encoded).is_canonical());
+}
+
+#[test]
+fn every_warning() {
+    let mut encoded = assemble(
+        "
+        HEADER 8 1 RGBA SRGB
+        RGBA 1 2 3 255
+        RUN 3
+        RUN 2
+        RGB 1 2 3
+        RUN 5
+        INDEX 0
+        END
+        ",
+    )
+    .unwrap();
+    encoded.extend_from_slice(&[1, 2, 3]);
+
+    let report = validate(&encoded);
+
+    assert!(report.is_valid());
+    assert!(!report.is_canonical());
+    let warning = |offset, kind| Warning { offset, kind };
+    assert_eq!(
+        report.warnings,
+        [
+            warning(14, WarningKind::UnchangedAlpha),
+            warning(
+                14,
+                WarningKind::Oversized {
+                    op: Op::Luma {
+                        dg: 2,
+                        dr_dg: -1,
+                        db_dg: 1
+                    }
+                }
+            ),
+            warning(20, WarningKind::SplitRun),
+            warning(21, WarningKind::Oversized { op: Op::Run(1) }),
+            warning(25, WarningKind::RunPastEnd),
+            warning(26, WarningKind::OpPastEnd),
+            warning(35, WarningKind::TrailingData { len: 3 }),
+        ]
+    );
+    // unlike the reference decoder, ours wants the end marker right after the last pixel
+    assert_eq!(try_decode(&encoded), Err(QoiError::MissingEndMarker));
+}
+
+#[test]
+fn runs_too_long_to_merge() {
+    let encoded = assemble(
+        "
+        HEADER 70 1 RGB SRGB
+        RUN 30
+        RUN 40
+        END
+        ",
+    )
+    .unwrap();
+
+    assert!(validate(&encoded).is_canonical());
+}
+
+#[test]
+fn errors() {
+    let pixels = (0..4).map(|_| Pixel::random()).collect::<Vec<_>>();
+    let encoded = encode_from_pix(&pixels, 2, 2);
+
+    let report = validate(&encoded[..5]);
+    assert_eq!(report.header, None);
+    assert_eq!(
+        report.error,
+        Some(DecodeFailure {
+            offset: 0,
+            error: QoiError::Truncated { offset: 5 },
+            pixels_decoded: 0
+        })
+    );
+
+    let report = validate(&encoded[..encoded.len() - 10]);
+    assert!(report.header.is_some());
+    assert!(matches!(
+        report.error,
+        Some(DecodeFailure {
+            error: QoiError::Truncated { .. },
+            ..
+        })
+    ));
+
+    let mut broken = encoded.clone();
+    let len = broken.len();
+    broken[len - 1] = 2;
+    assert_eq!(
+        validate(&broken).error,
+        Some(DecodeFailure {
+            offset: len - 8,
+            error: QoiError::MissingEndMarker,
+            pixels_decoded: 4
+        })
+    );
+}
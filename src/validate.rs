@@ -0,0 +1,237 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    decoder::DecoderState,
+    encoder::{fits_diff, fits_luma},
+    num_pixels, Chunk, DecodeFailure, Op, Pixel, QOIHash, QoiError, QoiHeader, END_MARKER,
+};
+
+/// Something the spec allows, but that an encoder like the one in this crate does not write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// A run right after another run, with at most 62 pixels in both,
+    /// so the two could have been one op.
+    SplitRun,
+    /// An OpRGBA that keeps the alpha of the previous pixel.
+    UnchangedAlpha,
+    /// An op whose pixel fits into the smaller `op`.
+    Oversized { op: Op },
+    /// A run that goes past the last pixel and is cut off.
+    RunPastEnd,
+    /// An op after the last pixel, before the end marker.
+    ///
+    /// The reference decoder ignores everything after the last pixel,
+    /// but the decoders in this crate expect the end marker right there and fail.
+    OpPastEnd,
+    /// `len` bytes after the end marker.
+    TrailingData { len: usize },
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::SplitRun => write!(f, "run that fits into the previous one"),
+            WarningKind::UnchangedAlpha => write!(f, "OpRGBA with the alpha of the previous pixel"),
+            WarningKind::Oversized { op } => write!(f, "could have been `{}`", op),
+            WarningKind::RunPastEnd => write!(f, "run goes past the last pixel"),
+            WarningKind::OpPastEnd => write!(f, "op after the last pixel"),
+            WarningKind::TrailingData { len } => write!(f, "{} bytes after the end marker", len),
+        }
+    }
+}
+
+/// A [`WarningKind`] and the byte at which the op or data it is about starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Warning {
+    pub offset: usize,
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: {}", self.offset, self.kind)
+    }
+}
+
+/// What [`validate`] found in a stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// `None` if the header is invalid.
+    pub header: Option<QoiHeader>,
+    /// The error that stopped validation, `None` if the stream follows the spec.
+    pub error: Option<DecodeFailure>,
+    /// In the order they appear in the stream, up to the error if there is one.
+    pub warnings: Vec<Warning>,
+}
+
+impl ValidationReport {
+    /// Whether the stream follows the spec, even if there are warnings.
+    /// The decoders in this crate still reject it if there is a [`WarningKind::OpPastEnd`].
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Whether the stream follows the spec and there is nothing to warn about.
+    pub fn is_canonical(&self) -> bool {
+        self.is_valid() && self.warnings.is_empty()
+    }
+}
+
+/// Checks `encoded` for errors, and for everything that is legal but unusual, see [`WarningKind`].
+///
+/// Nothing is allocated for the pixels, so this is safe to run on untrusted input without limits.
+pub fn validate(encoded: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport {
+        header: None,
+        error: None,
+        warnings: Vec::new(),
+    };
+    let header = match QoiHeader::from_u8(encoded) {
+        Ok(header) => header,
+        Err(error) => {
+            report.error = Some(DecodeFailure {
+                offset: 0,
+                error,
+                pixels_decoded: 0,
+            });
+            return report;
+        }
+    };
+    report.header = Some(header.clone());
+    if let Err(failure) = check_ops(encoded, &header, &mut report.warnings) {
+        report.error = Some(failure);
+    }
+    report
+}
+
+fn check_ops(
+    encoded: &[u8],
+    header: &QoiHeader,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), DecodeFailure> {
+    let num_pixels = num_pixels(header.width, header.height).map_err(|error| DecodeFailure {
+        offset: 0,
+        error,
+        pixels_decoded: 0,
+    })?;
+    let mut warn = |offset, kind| warnings.push(Warning { offset, kind });
+
+    let mut state = DecoderState::new();
+    // what an encoder has in its hash, which unlike the decoder only inserts pixels it did not find
+    let mut hash = QOIHash::new();
+    let mut decoded = 0;
+    let mut previous_run = None;
+    let mut i = 14;
+    while decoded < num_pixels {
+        let chunk = Chunk::from_encoding(&encoded[i..]).ok_or(DecodeFailure {
+            offset: i,
+            error: QoiError::Truncated {
+                offset: encoded.len(),
+            },
+            pixels_decoded: decoded,
+        })?;
+        let op = Op::from(&chunk);
+
+        if let Op::RGBA { a, .. } = op {
+            if a == state.previous.a {
+                warn(i, WarningKind::UnchangedAlpha);
+            }
+        }
+        if let (Some(previous), Op::Run(run)) = (previous_run, op) {
+            if previous + run <= 62 {
+                warn(i, WarningKind::SplitRun);
+            }
+        }
+        let previous = state.previous;
+        let count = state.decode_chunk(&chunk);
+        if let Op::RGB { .. } | Op::RGBA { .. } | Op::Luma { .. } = op {
+            let smallest = smallest_op(previous, &mut hash, state.previous);
+            if smallest.size() < op.size() {
+                warn(i, WarningKind::Oversized { op: smallest });
+            }
+        }
+        if !matches!(op, Op::Index(_) | Op::Run(_)) {
+            hash.insert(&state.previous);
+        }
+        if count > num_pixels - decoded {
+            warn(i, WarningKind::RunPastEnd);
+        }
+
+        previous_run = match op {
+            Op::Run(run) => Some(run),
+            _ => None,
+        };
+        decoded += usize::min(count, num_pixels - decoded);
+        i += op.size();
+    }
+
+    // ops after the last pixel are fine as long as the end marker follows eventually
+    let last_pixel = i;
+    let mut past_end = Vec::new();
+    while encoded.get(i..i + END_MARKER.len()) != Some(&END_MARKER[..]) {
+        match Op::parse(encoded.get(i..).unwrap_or_default()) {
+            Some(op) => {
+                past_end.push(i);
+                i += op.size();
+            }
+            None => {
+                return Err(DecodeFailure {
+                    offset: last_pixel,
+                    error: QoiError::MissingEndMarker,
+                    pixels_decoded: decoded,
+                })
+            }
+        }
+    }
+    for offset in past_end {
+        warn(offset, WarningKind::OpPastEnd);
+    }
+
+    let end = i + END_MARKER.len();
+    if encoded.len() > end {
+        warn(
+            end,
+            WarningKind::TrailingData {
+                len: encoded.len() - end,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// The op the encoder in this crate would write for `pixel` after `previous`, with `hash` as the
+/// encoder's hash, ignoring runs longer than one.
+fn smallest_op(previous: Pixel, hash: &mut QOIHash, pixel: Pixel) -> Op {
+    if pixel == previous {
+        return Op::Run(1);
+    }
+    if let Some(index) = hash.lookup(&pixel) {
+        return Op::Index(index);
+    }
+    let dr = pixel.r.wrapping_sub(previous.r) as i8;
+    let dg = pixel.g.wrapping_sub(previous.g) as i8;
+    let db = pixel.b.wrapping_sub(previous.b) as i8;
+    if pixel.a != previous.a {
+        Op::RGBA {
+            r: pixel.r,
+            g: pixel.g,
+            b: pixel.b,
+            a: pixel.a,
+        }
+    } else if fits_diff(dr, dg, db) {
+        Op::Diff { dr, dg, db }
+    } else if fits_luma(dr, dg, db) {
+        Op::Luma {
+            dg,
+            dr_dg: dr.wrapping_sub(dg),
+            db_dg: db.wrapping_sub(dg),
+        }
+    } else {
+        Op::RGB {
+            r: pixel.r,
+            g: pixel.g,
+            b: pixel.b,
+        }
+    }
+}
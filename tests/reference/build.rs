@@ -0,0 +1,8 @@
+fn main() {
+    println!("cargo:rerun-if-changed=c/qoi.c");
+    println!("cargo:rerun-if-changed=c/qoi.h");
+    cc::Build::new()
+        .file("c/qoi.c")
+        .opt_level(2)
+        .compile("qoi_reference");
+}
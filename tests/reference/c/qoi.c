@@ -0,0 +1,8 @@
+#define QOI_IMPLEMENTATION
+#define QOI_NO_STDIO
+#include "qoi.h"
+
+/* buffers returned by qoi_encode and qoi_decode have to be freed with the same allocator */
+void qoi_free(void *ptr) {
+	QOI_FREE(ptr);
+}
@@ -0,0 +1,68 @@
+//! Bindings to `qoi_encode` and `qoi_decode` from the reference implementation in `c/qoi.h`,
+//! so the codec can be tested against it.
+
+use std::os::raw::{c_int, c_void};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Desc {
+    pub width: u32,
+    pub height: u32,
+    pub channels: u8,
+    pub colorspace: u8,
+}
+
+extern "C" {
+    fn qoi_encode(data: *const c_void, desc: *const Desc, out_len: *mut c_int) -> *mut c_void;
+    fn qoi_decode(data: *const c_void, size: c_int, desc: *mut Desc, channels: c_int)
+        -> *mut c_void;
+    fn qoi_free(ptr: *mut c_void);
+}
+
+/// Copies a buffer returned by the reference implementation and frees it.
+///
+/// # Safety
+/// `ptr` has to point to `len` bytes allocated by the reference implementation.
+unsafe fn take(ptr: *mut c_void, len: usize) -> Vec<u8> {
+    let bytes = std::slice::from_raw_parts(ptr as *const u8, len).to_vec();
+    qoi_free(ptr);
+    bytes
+}
+
+/// Encodes `pixels`, which hold `desc.channels` bytes per pixel.
+/// Returns `None` if the reference rejects the image.
+pub fn encode(pixels: &[u8], desc: Desc) -> Option<Vec<u8>> {
+    let expected = desc.width as usize * desc.height as usize * desc.channels as usize;
+    assert_eq!(pixels.len(), expected, "pixels do not match the description");
+    let mut len = 0;
+    // SAFETY: `pixels` is as large as qoi_encode reads according to `desc`
+    unsafe {
+        let encoded = qoi_encode(pixels.as_ptr() as *const c_void, &desc, &mut len);
+        if encoded.is_null() {
+            return None;
+        }
+        Some(take(encoded, len as usize))
+    }
+}
+
+/// Decodes `encoded` into `channels` bytes per pixel, or 0 for the channels from the header.
+/// Returns `None` if the reference rejects the stream.
+pub fn decode(encoded: &[u8], channels: u8) -> Option<(Desc, Vec<u8>)> {
+    let size = c_int::try_from(encoded.len()).ok()?;
+    let mut desc = Desc::default();
+    // SAFETY: qoi_decode reads at most `size` bytes and fills in `desc` on success
+    unsafe {
+        let decoded = qoi_decode(
+            encoded.as_ptr() as *const c_void,
+            size,
+            &mut desc,
+            channels as c_int,
+        );
+        if decoded.is_null() {
+            return None;
+        }
+        let channels = if channels == 0 { desc.channels } else { channels };
+        let len = desc.width as usize * desc.height as usize * channels as usize;
+        Some((desc, take(decoded, len)))
+    }
+}